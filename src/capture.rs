@@ -0,0 +1,300 @@
+//! Opt-in capturing of the raw payloads received from edupage, for debugging.
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::{
+    collections::HashMap,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use std::{fmt, path::PathBuf, sync::Arc};
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde_json::Value;
+
+/// What kind of payload was captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    /// The user's data from the userhome page (json).
+    Userhome,
+    /// The response of the substitution viewer.
+    Substitution,
+    /// The response of [`crate::traits::Cloud::upload`].
+    CloudUpload,
+    /// The response of signing into an online lesson.
+    OnlineLesson,
+    /// The response of a call made with [`crate::traits::Rpc::rpc`].
+    Rpc,
+}
+
+impl PayloadKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayloadKind::Userhome => "userhome",
+            PayloadKind::Substitution => "substitution",
+            PayloadKind::CloudUpload => "cloud_upload",
+            PayloadKind::OnlineLesson => "online_lesson",
+            PayloadKind::Rpc => "rpc",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CapturedPayload {
+    pub kind: PayloadKind,
+    pub content: String,
+}
+
+/// Where the captured payloads are sent.
+#[derive(Clone)]
+pub enum CaptureSink {
+    /// Every payload is written into a new file in the directory, named
+    /// `{kind}-{unix time in nanoseconds}.json`. The directory is created if it does not exist.
+    Directory(PathBuf),
+    Callback(Arc<dyn Fn(&CapturedPayload) + Send + Sync>),
+}
+
+impl fmt::Debug for CaptureSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureSink::Directory(path) => f.debug_tuple("Directory").field(path).finish(),
+            CaptureSink::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// Captures the raw payloads edupage sends (like the userhome json), which is useful when
+/// parsing them fails. Nothing is captured by default, enable it with
+/// [`crate::edupage::EdupageBuilder::debug_capture`].
+///
+/// The payloads contain personal data (names, ids, messages). With [`DebugCapture::redact`],
+/// names are replaced with `REDACTED` and ids with made-up ones in json payloads (including the
+/// json inside the timeline items' `data`) before they are captured. Html inside the payloads
+/// is not redacted.
+///
+/// Capturing never makes a request fail, errors while writing the files are ignored.
+///
+/// Example usage:
+#[cfg_attr(feature = "blocking", doc = "```")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use cdupage::{capture::DebugCapture, edupage::Edupage};
+///
+/// let edupage = Edupage::builder()
+///     .debug_capture(DebugCapture::directory("edupage-dumps").redact(true))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(not(any(feature = "blocking", feature = "async")), allow(dead_code))]
+pub struct DebugCapture {
+    sink: CaptureSink,
+    redact: bool,
+}
+
+impl DebugCapture {
+    pub fn new(sink: CaptureSink) -> Self {
+        Self {
+            sink,
+            redact: false,
+        }
+    }
+
+    pub fn directory(path: impl Into<PathBuf>) -> Self {
+        Self::new(CaptureSink::Directory(path.into()))
+    }
+
+    pub fn callback(callback: impl Fn(&CapturedPayload) + Send + Sync + 'static) -> Self {
+        Self::new(CaptureSink::Callback(Arc::new(callback)))
+    }
+
+    /// Redact names and ids in json payloads. Disabled by default.
+    pub fn redact(mut self, enabled: bool) -> Self {
+        self.redact = enabled;
+        self
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn capture(&self, kind: PayloadKind, content: &str) {
+        let content = if self.redact {
+            redact(content)
+        } else {
+            content.to_string()
+        };
+
+        let payload = CapturedPayload { kind, content };
+
+        match &self.sink {
+            CaptureSink::Directory(directory) => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or_default();
+                let path = directory.join(format!("{}-{}.json", kind.as_str(), timestamp));
+
+                let _ = fs::create_dir_all(directory)
+                    .and_then(|_| fs::write(path, payload.content.as_bytes()));
+            }
+            CaptureSink::Callback(callback) => callback(&payload),
+        }
+    }
+}
+
+/// Keys with names of people that do not end with `meno` or `name`, see [`is_name_key`].
+#[cfg(any(feature = "blocking", feature = "async"))]
+const NAME_KEYS: [&str; 2] = ["meninyDnes", "meninyZajtra"];
+
+/// Keys with ids of users, like `"user": "Student1234"`.
+#[cfg(any(feature = "blocking", feature = "async"))]
+const USER_KEYS: [&str; 3] = ["user", "target_user", "vlastnik"];
+
+/// Keys with ids edupage uses, like `"classid": "12"` or `"teacherids": ["1", "2"]`.
+#[cfg(any(feature = "blocking", feature = "async"))]
+const ID_KEYS: [&str; 20] = [
+    "id",
+    "userid",
+    "studentid",
+    "studentids",
+    "teacherid",
+    "teacher2id",
+    "teacherids",
+    "parentid",
+    "parent1id",
+    "parent2id",
+    "parent3id",
+    "classid",
+    "classids",
+    "classroomid",
+    "classroomids",
+    "subjectid",
+    "subjectids",
+    "groupids",
+    "timelineid",
+    "cloudid",
+];
+
+/// Keys of other ids are assumed to be written like `student_id` or `studentIds`, so keys like
+/// `paid` or `valid` are left alone.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn is_id_key(key: &str) -> bool {
+    USER_KEYS.contains(&key)
+        || ID_KEYS.contains(&key)
+        || ["_id", "_ids", "Id", "Ids"]
+            .iter()
+            .any(|suffix| key.ends_with(suffix))
+}
+
+/// Names of people are under keys like `firstname`, `meno` or `vlastnik_meno` ("meno" is
+/// Slovak for name). A plain `name` is the name of a subject, a class or a classroom, so it is
+/// kept.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn is_name_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+
+    NAME_KEYS
+        .iter()
+        .any(|name_key| name_key.eq_ignore_ascii_case(&key))
+        || key.ends_with("meno")
+        || (key.ends_with("name") && key != "name")
+}
+
+/// Keys with json encoded into a string, like the `data` of the timeline items.
+#[cfg(any(feature = "blocking", feature = "async"))]
+const NESTED_JSON_KEYS: [&str; 1] = ["data"];
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+const REDACTED: &str = "REDACTED";
+
+/// Redacts names and ids in a json payload, other payloads are returned as they are.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn redact(content: &str) -> String {
+    let mut value: Value = match serde_json::from_str(content) {
+        Ok(x) => x,
+        Err(_) => return content.to_string(),
+    };
+
+    let mut redactor = Redactor::default();
+    redactor.redact_value(&mut value);
+
+    value.to_string()
+}
+
+/// Replaces the ids consistently, so the references between the redacted objects still work.
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Default)]
+struct Redactor {
+    ids: HashMap<String, String>,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl Redactor {
+    fn redact_value(&mut self, value: &mut Value) {
+        match value {
+            Value::Array(values) => values.iter_mut().for_each(|v| self.redact_value(v)),
+            Value::Object(map) => {
+                let entries = std::mem::take(map);
+
+                for (key, mut value) in entries {
+                    if is_name_key(&key) && value.is_string() {
+                        value = Value::String(REDACTED.to_string());
+                    } else if is_id_key(&key) {
+                        self.redact_ids(&mut value);
+                    } else if NESTED_JSON_KEYS.contains(&key.as_str()) && value.is_string() {
+                        self.redact_nested_json(&mut value);
+                    } else {
+                        self.redact_value(&mut value);
+                    }
+
+                    // the dbi uses the ids as keys
+                    let key = if !key.is_empty() && key.chars().all(|c| c.is_ascii_digit()) {
+                        self.redact_id(&key)
+                    } else {
+                        key
+                    };
+
+                    map.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn redact_nested_json(&mut self, value: &mut Value) {
+        let Value::String(json) = value else {
+            return;
+        };
+
+        if let Ok(mut nested) = serde_json::from_str::<Value>(json) {
+            self.redact_value(&mut nested);
+            *json = nested.to_string();
+        }
+    }
+
+    fn redact_ids(&mut self, value: &mut Value) {
+        match value {
+            Value::String(id) => *id = self.redact_id(id),
+            Value::Number(id) => {
+                if let Ok(redacted) = self.redact_id(&id.to_string()).parse() {
+                    *id = redacted;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.redact_ids(v)),
+            _ => {}
+        }
+    }
+
+    /// `Student1234` => `Student1`, the prefix (type of the user) is kept.
+    fn redact_id(&mut self, id: &str) -> String {
+        let prefix: String = id.chars().take_while(|c| !c.is_ascii_digit()).collect();
+        let number = &id[prefix.len()..];
+
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return id.to_string();
+        }
+
+        let next = self.ids.len() + 1;
+        let redacted = self
+            .ids
+            .entry(number.to_string())
+            .or_insert_with(|| next.to_string());
+
+        format!("{}{}", prefix, redacted)
+    }
+}
@@ -11,10 +11,10 @@ pub mod year_month_day_optional {
     where
         S: Serializer,
     {
-        if date.is_none() {
-            return serializer.serialize_none();
-        }
-        let date = date.unwrap();
+        let date = match date {
+            Some(date) => date,
+            None => return serializer.serialize_none(),
+        };
 
         let s = format!("{}", date.format(FORMAT));
         serializer.serialize_str(&s)
@@ -29,10 +29,10 @@ pub mod year_month_day_optional {
             Err(_) => return Ok(None),
         };
 
-        if s.is_none() {
-            return Ok(None);
-        }
-        let s = s.unwrap();
+        let s = match s {
+            Some(s) => s,
+            None => return Ok(None),
+        };
 
         if s.is_empty() {
             Ok(None)
@@ -55,11 +55,11 @@ pub mod javascript_date_format_option {
     where
         S: Serializer,
     {
-        if date.is_none() {
-            return serializer.serialize_none();
-        }
+        let date = match date {
+            Some(date) => date,
+            None => return serializer.serialize_none(),
+        };
 
-        let date = date.unwrap();
         let s = format!("{}", date.format(FORMAT));
         serializer.serialize_str(&s)
     }
@@ -70,13 +70,14 @@ pub mod javascript_date_format_option {
     {
         let s: Option<String> = Deserialize::deserialize(deserializer)?;
 
-        if s.is_none() {
-            return Ok(None);
-        }
-        let s = s.unwrap();
-        match NaiveDateTime::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom) {
+        let s = match s {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        match NaiveDateTime::parse_from_str(&s, FORMAT) {
             Ok(x) => Ok(Some(x)),
-            Err(e) => Err(e),
+            Err(e) => Err(serde::de::Error::custom(e)),
         }
     }
 }
@@ -87,19 +88,24 @@ where
 {
     let s: &str = &String::deserialize(deserializer)?;
 
-    let time: Vec<&str> = s.split(":").collect();
+    let (hours, minutes) = match s.split_once(":") {
+        Some(x) => x,
+        None => return Err(serde::de::Error::custom(format!("Invalid time: {}", s))),
+    };
 
-    let hours: u32 = match time[0].parse() {
+    let hours: u32 = match hours.parse() {
         Ok(x) => x,
         Err(_) => return Err(serde::de::Error::custom("Failed to parse hours")),
     };
-    let minutes: u32 = match time[1].parse() {
+    let minutes: u32 = match minutes.parse() {
         Ok(x) => x,
         Err(_) => return Err(serde::de::Error::custom("Failed to parse minutes")),
     };
 
-    Ok(NaiveDateTime::new(
-        Local::now().date_naive(),
-        NaiveTime::from_hms_opt(hours, minutes, 0).unwrap(),
-    ))
+    let time = match NaiveTime::from_hms_opt(hours, minutes, 0) {
+        Some(x) => x,
+        None => return Err(serde::de::Error::custom(format!("Invalid time: {}", s))),
+    };
+
+    Ok(NaiveDateTime::new(Local::now().date_naive(), time))
 }
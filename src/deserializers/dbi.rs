@@ -1,39 +1,9 @@
-use serde::{de::DeserializeOwned, ser, Deserialize, Deserializer, Serialize};
-use serde_json::{Map, Value};
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
 
-use crate::types::dbi::DBIBase;
-
-pub mod gender {
-    use serde::{self, Deserialize, Deserializer, Serializer};
-
-    use crate::types::dbi::Gender;
-
-    pub fn serialize<S>(gender: &Gender, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(match *gender {
-            Gender::Male => "M",
-            Gender::Female => "F",
-        })
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Gender, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s: &str = &String::deserialize(deserializer)?.to_lowercase();
-
-        match s {
-            "m" => Ok(Gender::Male),
-            "f" => Ok(Gender::Female),
-            _ => Err(serde::de::Error::custom(format!(
-                "Failed to deserialize gender: {}",
-                s
-            ))),
-        }
-    }
-}
+use serde::{
+    de::{IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
 
 pub mod gender_option {
     use std::borrow::Cow;
@@ -46,10 +16,11 @@ pub mod gender_option {
     where
         S: Serializer,
     {
-        if gender.is_none() {
-            return serializer.serialize_none();
-        }
-        let gender = gender.unwrap();
+        let gender = match gender {
+            Some(gender) => gender,
+            None => return serializer.serialize_none(),
+        };
+
         serializer.serialize_str(match gender {
             Gender::Male => "M",
             Gender::Female => "F",
@@ -60,18 +31,12 @@ pub mod gender_option {
     where
         D: Deserializer<'de>,
     {
-        let s: &Option<Cow<str>> = &Deserialize::deserialize(deserializer)?;
-
-        if s.is_none() {
-            return Ok(None);
-        }
+        let s: Option<Cow<str>> = Deserialize::deserialize(deserializer)?;
 
         let string = match s {
-            Some(val) => val,
-            None => unreachable!(),
-        }
-        .as_ref()
-        .to_lowercase();
+            Some(val) => val.to_lowercase(),
+            None => return Ok(None),
+        };
 
         if string.is_empty() {
             return Ok(None);
@@ -90,39 +55,39 @@ pub mod gender_option {
     }
 }
 
-impl Serialize for DBIBase {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let j = serde_json::to_string(self).map_err(ser::Error::custom)?;
-        j.serialize(serializer)
-    }
-}
-
+/// Deserializes the entries of a dbi map (`{"id": {...}, ...}`), ordered by their ids.
+/// Edupage sends an empty array instead of an empty map.
 pub fn deserialize_dbi_base<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     D: Deserializer<'de>,
-    T: DeserializeOwned,
+    T: Deserialize<'de>,
 {
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    #[allow(dead_code)]
-    enum MapOrVec<T> {
-        Map(Map<String, Value>),
-        Vec(Vec<T>),
-    }
+    struct DBIVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for DBIVisitor<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of dbi entries")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            // the entries are deserialized directly (not through a `Value`), so the errors
+            // point to the entry and the field that could not be deserialized
+            let mut entries = BTreeMap::new();
+            while let Some((id, entry)) = map.next_entry::<String, T>()? {
+                entries.insert(id, entry);
+            }
 
-    let ts: Map<String, Value> = match MapOrVec::<T>::deserialize(deserializer)? {
-        MapOrVec::Map(m) => m,
-        MapOrVec::Vec(_) => return Ok(Vec::new()),
-    };
+            Ok(entries.into_values().collect())
+        }
 
-    let mut output: Vec<T> = Vec::new();
-    for v in ts.values() {
-        let t: T = serde_json::from_value(v.clone()).unwrap();
-        output.push(t);
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+
+            Ok(Vec::new())
+        }
     }
 
-    Ok(output)
+    deserializer.deserialize_any(DBIVisitor(PhantomData))
 }
@@ -16,10 +16,9 @@ pub mod string_i64_option {
     where
         S: Serializer,
     {
-        if id.is_none() {
-            serializer.serialize_none()
-        } else {
-            serializer.serialize_i64(id.unwrap())
+        match id {
+            Some(id) => serializer.serialize_i64(*id),
+            None => serializer.serialize_none(),
         }
     }
 
@@ -32,11 +31,11 @@ pub mod string_i64_option {
             Err(_) => return Ok(None),
         };
 
-        if s.is_none() {
-            return Ok(None);
-        }
+        let s = match s {
+            Some(s) => s,
+            None => return Ok(None),
+        };
 
-        let s = &s.unwrap();
         if s.is_empty() {
             return Ok(None);
         }
@@ -55,11 +54,10 @@ pub mod string_i64_vec_option {
     where
         S: Serializer,
     {
-        if vec.is_none() {
-            return serializer.serialize_none();
-        }
-
-        let vec = vec.clone().unwrap();
+        let vec = match vec {
+            Some(vec) => vec,
+            None => return serializer.serialize_none(),
+        };
 
         let mut seq = serializer.serialize_seq(Some(vec.len()))?;
         for item in vec {
@@ -74,11 +72,11 @@ pub mod string_i64_vec_option {
     {
         let s: Option<Vec<String>> = Deserialize::deserialize(deserializer)?;
 
-        if s.is_none() {
-            return Ok(None);
-        }
+        let seq = match s {
+            Some(seq) => seq,
+            None => return Ok(None),
+        };
 
-        let seq = s.unwrap();
         if seq.is_empty() {
             return Ok(Some(Vec::new()));
         }
@@ -45,7 +45,7 @@ fn parse_userid(s: &str) -> Option<UserID> {
         }
     }
 
-    let id: i64 = id.parse().unwrap(); // should always be a number
+    let id: i64 = id.parse().ok()?;
     let user_type: &str = &user_type;
 
     Some(match user_type {
@@ -79,11 +79,12 @@ impl<'de> Deserialize<'de> for UserID {
     {
         let s: &str = &String::deserialize(deserializer)?;
 
-        let user_id = parse_userid(s);
-        if user_id.is_none() {
-            return Err(serde::de::Error::custom("Unexpected user type".to_string()));
+        match parse_userid(s) {
+            Some(user_id) => Ok(user_id),
+            None => Err(serde::de::Error::custom(format!(
+                "Unexpected user id {}",
+                s
+            ))),
         }
-
-        Ok(user_id.unwrap())
     }
 }
@@ -2,7 +2,8 @@ use num_enum::TryFromPrimitiveError;
 
 use crate::types::timeline::TimelineItemType;
 
-pub const TIMELINE_ITEM_TYPE_NAMES: [&str; 19] = [
+/// The names of the [`TimelineItemType`]s, indexed by their value.
+pub const TIMELINE_ITEM_TYPE_NAMES: [&str; 20] = [
     "news",
     "sprava",
     "h_dailyplan",
@@ -21,6 +22,7 @@ pub const TIMELINE_ITEM_TYPE_NAMES: [&str; 19] = [
     "h_znamky",
     "homework",
     "h_cleardbi",
+    "unknown",
     "testpridelenie",
 ];
 
@@ -46,7 +48,10 @@ impl TimelineItemType {
     }
 
     pub fn key_name_for_n(n: usize) -> &'static str {
-        TIMELINE_ITEM_TYPE_NAMES[n]
+        TIMELINE_ITEM_TYPE_NAMES
+            .get(n)
+            .copied()
+            .unwrap_or("unknown")
     }
 }
 
@@ -55,15 +60,11 @@ pub mod timeline_item_type {
 
     use crate::types::timeline::TimelineItemType;
 
-    use super::TIMELINE_ITEM_TYPE_NAMES;
-
     pub fn serialize<S>(item_type: &TimelineItemType, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let integer_value = *item_type as usize;
-
-        serializer.serialize_str(TIMELINE_ITEM_TYPE_NAMES[integer_value])
+        serializer.serialize_str(item_type.as_str())
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<TimelineItemType, D::Error>
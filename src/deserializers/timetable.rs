@@ -7,11 +7,10 @@ pub mod plan_item_type_option {
     where
         S: Serializer,
     {
-        if item.is_none() {
-            return serializer.serialize_none();
-        }
-
-        let item = item.unwrap();
+        let item = match item {
+            Some(item) => item,
+            None => return serializer.serialize_none(),
+        };
 
         match item {
             PlanItemType::Period => serializer.serialize_str("period"),
@@ -48,11 +47,10 @@ pub mod hh_mm_naivedatetime_option {
     where
         S: Serializer,
     {
-        if item.is_none() {
-            return serializer.serialize_none();
-        }
-
-        let item = item.unwrap();
+        let item = match item {
+            Some(item) => item,
+            None => return serializer.serialize_none(),
+        };
 
         serializer.serialize_str(&format!("{}:{}", item.hour(), item.minute()))
     }
@@ -68,8 +66,8 @@ pub mod hh_mm_naivedatetime_option {
 
         let mut parts = value.split(":");
 
-        let hour = match parts.next() {
-            Some(h) => h.parse::<u32>().unwrap(),
+        let hour = match parts.next().and_then(|h| h.parse::<u32>().ok()) {
+            Some(h) => h,
             None => {
                 return Err(serde::de::Error::custom(format!(
                     "Failed to deserialize hour from {value}"
@@ -77,8 +75,8 @@ pub mod hh_mm_naivedatetime_option {
             }
         };
 
-        let minute = match parts.next() {
-            Some(h) => h.parse::<u32>().unwrap(),
+        let minute = match parts.next().and_then(|m| m.parse::<u32>().ok()) {
+            Some(m) => m,
             None => {
                 return Err(serde::de::Error::custom(format!(
                     "Failed to deserialize minute from {value}"
@@ -1,66 +1,865 @@
-use std::{fs::File, io::Write};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::path::Path;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "blocking")]
+use std::thread;
+use std::time::Duration;
 
+#[cfg(any(feature = "blocking", feature = "async"))]
 use reqwest::{
-    blocking::{Client, Response},
-    header::{HeaderMap, HeaderName, HeaderValue},
+    cookie::{CookieStore, Jar},
+    header::HeaderValue,
+    Certificate, Proxy, Url,
 };
 use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "blocking", feature = "async"))]
 use std::collections::HashMap;
 
+use crate::capture::DebugCapture;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::capture::PayloadKind;
+pub use crate::error::{EdupageError, ErrorDetails};
+use crate::parsing::ParseMode;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::parsing::{parse_user_data, ParseWarning};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::traits::CredentialSource;
+#[cfg(feature = "async")]
+use crate::transport::{AsyncReqwestTransport, AsyncTransport};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::transport::{HttpRequest, HttpResponse, OfflineTransport, RateLimiter, RetryPolicy};
+#[cfg(feature = "blocking")]
+use crate::transport::{ReqwestTransport, Transport};
+#[cfg(feature = "blocking")]
+use crate::types::SessionCookie;
 use crate::types::UserData;
 
+/// Clones share the session cookies, like clones of the http client do. Everything else (the
+/// user's data, the gsec hash, ...) is copied, so every clone keeps the hash that belongs to its
+/// data. Logging in or out on one clone ends the session of the others, which then fail with
+/// [`EdupageError::SessionExpired`] (or log in again, see [`crate::traits::Reauthentication`]).
+#[cfg(feature = "blocking")]
 #[derive(Clone)]
 pub struct Edupage {
     pub(crate) is_logged_in: bool,
-    pub(crate) client: reqwest::blocking::Client,
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) cookies: Arc<SessionCookies>,
     pub(crate) data: Option<UserData>,
-    pub(crate) gsec_hash: Option<String>,
+    pub(crate) gsec_hash: GsecHash,
+    pub(crate) reauthentication: Option<CredentialSource>,
+    pub(crate) active_child: Option<i64>,
+    pub(crate) host: EdupageHost,
+    pub(crate) capture: Option<DebugCapture>,
+    pub(crate) parse_mode: ParseMode,
+    pub(crate) parse_warnings: Vec<ParseWarning>,
+    pub(crate) offline: bool,
     pub subdomain: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum EdupageError {
-    InvalidCredentials,
-    HTTPError(String),
-    InvalidResponse,
-    ParseError(String),
-    SerializationError(String),
-    NotLoggedIn,
-    MissingData,
-    Other(String),
+/// Where edupage's servers are, by default `https://{subdomain}.edupage.org`.
+#[derive(Debug, Clone)]
+pub(crate) struct EdupageHost {
+    scheme: String,
+    domain: String,
+    port: Option<u16>,
+    subdomain_in_host: bool,
 }
 
+impl Default for EdupageHost {
+    fn default() -> Self {
+        Self {
+            scheme: "https".to_string(),
+            domain: "edupage.org".to_string(),
+            port: None,
+            subdomain_in_host: true,
+        }
+    }
+}
+
+impl EdupageHost {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn base_url(&self, subdomain: &str) -> String {
+        let host = if self.subdomain_in_host {
+            format!("{}.{}", subdomain, self.domain)
+        } else {
+            self.domain.clone()
+        };
+
+        match self.port {
+            Some(port) => format!("{}://{}:{}", self.scheme, host, port),
+            None => format!("{}://{}", self.scheme, host),
+        }
+    }
+
+    /// `path` has to start with a `/`, e.g. `/user/`.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn url(&self, subdomain: &str, path: &str) -> String {
+        format!("{}{}", self.base_url(subdomain), path)
+    }
+
+    /// Returns the subdomain of an edupage url, e.g. `school` for `https://school.edupage.org/user/`.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn subdomain_from_url(&self, url: &Url) -> Option<String> {
+        if !self.subdomain_in_host {
+            return None;
+        }
+
+        let (subdomain, domain) = url.host_str()?.split_once('.')?;
+
+        if domain != self.domain {
+            return None;
+        }
+
+        Some(subdomain.to_string())
+    }
+}
+
+/// Options of the http client, set with [`EdupageBuilder`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientOptions {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    verbose: bool,
+}
+
+impl ClientOptions {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn proxy(&self) -> Result<Option<Proxy>, EdupageError> {
+        match &self.proxy {
+            Some(url) => Proxy::all(url)
+                .map(Some)
+                .map_err(|e| EdupageError::Other(format!("Invalid proxy: {}", e).into())),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn root_certificates(&self) -> Result<Vec<Certificate>, EdupageError> {
+        self.root_certificates
+            .iter()
+            .map(|pem| {
+                Certificate::from_pem(pem)
+                    .map_err(|e| EdupageError::Other(format!("Invalid certificate: {}", e).into()))
+            })
+            .collect()
+    }
+
+    /// The blocking client has no separate read timeout, so the shorter of the timeouts is used
+    /// for the whole request.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_client_builder(
+        &self,
+    ) -> Result<reqwest::blocking::ClientBuilder, EdupageError> {
+        let mut builder = reqwest::blocking::Client::builder().connection_verbose(self.verbose);
+
+        let timeout = match (self.timeout, self.read_timeout) {
+            (Some(timeout), Some(read_timeout)) => Some(timeout.min(read_timeout)),
+            (timeout, read_timeout) => timeout.or(read_timeout),
+        };
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = self.proxy()? {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        for certificate in self.root_certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        Ok(builder)
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn async_client_builder(&self) -> Result<reqwest::ClientBuilder, EdupageError> {
+        let mut builder = reqwest::Client::builder().connection_verbose(self.verbose);
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(read_timeout) = self.read_timeout {
+            builder = builder.read_timeout(read_timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = self.proxy()? {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        for certificate in self.root_certificates()? {
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Builds an [`Edupage`] with a custom configuration.
+///
+/// Example usage (a longer timeout for uploading big files, through a proxy):
+#[cfg_attr(feature = "blocking", doc = "```")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use std::time::Duration;
+/// use cdupage::edupage::Edupage;
+///
+/// let edupage = Edupage::builder()
+///     .timeout(Duration::from_secs(120))
+///     .connect_timeout(Duration::from_secs(10))
+///     .proxy("http://proxy.example.com:3128")
+///     .user_agent("my-school-bot/1.0")
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// Example usage (using a local mock server instead of edupage's servers):
+#[cfg_attr(feature = "blocking", doc = "```")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use cdupage::edupage::Edupage;
+///
+/// let edupage = Edupage::builder()
+///     .scheme("http")
+///     .domain("127.0.0.1")
+///     .port(8080)
+///     .subdomain_in_host(false)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct EdupageBuilder {
+    host: EdupageHost,
+    client_options: ClientOptions,
+    capture: Option<DebugCapture>,
+    parse_mode: ParseMode,
+    #[cfg(feature = "blocking")]
+    transport: Option<Arc<dyn Transport>>,
+    #[cfg(feature = "async")]
+    async_transport: Option<Arc<dyn AsyncTransport>>,
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    retry_policy: RetryPolicy,
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl std::fmt::Debug for EdupageBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EdupageBuilder")
+            .field("host", &self.host)
+            .field("client_options", &self.client_options)
+            .field("capture", &self.capture)
+            .field("parse_mode", &self.parse_mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EdupageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The url scheme, `https` by default.
+    pub fn scheme(mut self, scheme: &str) -> Self {
+        self.host.scheme = scheme.to_string();
+        self
+    }
+
+    /// The domain of edupage's servers, `edupage.org` by default.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.host.domain = domain.to_string();
+        self
+    }
+
+    /// The port, the default port of the scheme is used if not set.
+    pub fn port(mut self, port: u16) -> Self {
+        self.host.port = Some(port);
+        self
+    }
+
+    /// Whether the school's subdomain is prepended to the domain (`school.edupage.org`).
+    ///
+    /// Disable this when the server does not use subdomains, like a local mock server
+    /// (`127.0.0.1`). Enabled by default.
+    pub fn subdomain_in_host(mut self, enabled: bool) -> Self {
+        self.host.subdomain_in_host = enabled;
+        self
+    }
+
+    /// How long to wait for a connection to the server. No timeout by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_options.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// How long to wait for the server's response.
+    ///
+    /// The blocking client does not support a separate read timeout, it uses this as the timeout
+    /// for the whole request (like [`EdupageBuilder::timeout`]).
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.client_options.read_timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout for the whole request, from connecting until the response is read.
+    ///
+    /// It is 30 seconds by default for [`Edupage`] (no timeout for `AsyncEdupage`), which might
+    /// not be enough to upload large files with [`crate::traits::Cloud::upload`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client_options.timeout = Some(timeout);
+        self
+    }
+
+    /// Send all requests through a HTTP(S) proxy, e.g. `http://proxy.example.com:3128`.
+    ///
+    /// An invalid url makes [`EdupageBuilder::build`] and `build_async` fail.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.client_options.proxy = Some(url.to_string());
+        self
+    }
+
+    /// The `User-Agent` header sent with every request. No user agent is sent by default.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.client_options.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Trust an additional root certificate (in the PEM format), e.g. the certificate of
+    /// a corporate proxy.
+    ///
+    /// An invalid certificate makes [`EdupageBuilder::build`] and `build_async` fail.
+    pub fn add_root_certificate(mut self, pem: &[u8]) -> Self {
+        self.client_options.root_certificates.push(pem.to_vec());
+        self
+    }
+
+    /// Log the contents of the connections (with the `log` crate, at the trace level).
+    /// Disabled by default.
+    pub fn verbose(mut self, enabled: bool) -> Self {
+        self.client_options.verbose = enabled;
+        self
+    }
+
+    /// Capture the raw payloads received from edupage, see [`DebugCapture`]. Disabled by default.
+    pub fn debug_capture(mut self, capture: DebugCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// How strictly the user's data is parsed, see [`ParseMode`]. [`ParseMode::Strict`] by default.
+    ///
+    /// The values that were replaced or skipped while parsing in [`ParseMode::Lenient`] are
+    /// returned by `parse_warnings` after logging in.
+    pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = parse_mode;
+        self
+    }
+
+    /// Send the requests with a custom [`Transport`] instead of [`ReqwestTransport`].
+    ///
+    /// The options of the http client (timeouts, proxy, ...) are not used by custom transports.
+    #[cfg(feature = "blocking")]
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Send the requests of `AsyncEdupage` with a custom [`AsyncTransport`] instead of
+    /// [`AsyncReqwestTransport`].
+    ///
+    /// The options of the http client (timeouts, proxy, ...) are not used by custom transports.
+    #[cfg(feature = "async")]
+    pub fn async_transport(mut self, transport: impl AsyncTransport + 'static) -> Self {
+        self.async_transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// When and how often failed requests are retried, see [`RetryPolicy`].
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Limit how many requests are sent, see [`RateLimiter`]. There is no limit by default.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn build(mut self) -> Result<Edupage, EdupageError> {
+        let transport: Arc<dyn Transport> = match self.transport.take() {
+            Some(t) => t,
+            None => Arc::new(ReqwestTransport::from_options(&self.client_options)?),
+        };
+
+        Ok(self.build_with_transport(transport))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn build_with_transport(self, transport: Arc<dyn Transport>) -> Edupage {
+        Edupage {
+            is_logged_in: false,
+            data: None,
+            transport,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            cookies: Arc::new(SessionCookies::default()),
+            gsec_hash: GsecHash::default(),
+            reauthentication: None,
+            active_child: None,
+            host: self.host,
+            capture: self.capture,
+            parse_mode: self.parse_mode,
+            parse_warnings: Vec::new(),
+            offline: false,
+            subdomain: None,
+        }
+    }
+
+    /// Creates a read-only `Edupage` from a saved userhome page, see [`Edupage::from_snapshot`].
+    ///
+    /// Only the options that do not need a connection (like [`EdupageBuilder::parse_mode`])
+    /// are used, no http client is created.
+    #[cfg(feature = "blocking")]
+    pub fn build_from_snapshot(self, snapshot: &str) -> Result<Edupage, EdupageError> {
+        let (data, warnings) = parse_snapshot(snapshot, self.parse_mode)?;
+
+        let mut edupage = self.build_with_transport(Arc::new(OfflineTransport));
+        edupage.data = Some(data);
+        edupage.parse_warnings = warnings;
+        edupage.is_logged_in = true;
+        edupage.offline = true;
+
+        Ok(edupage)
+    }
+
+    #[cfg(feature = "async")]
+    pub fn build_async(mut self) -> Result<AsyncEdupage, EdupageError> {
+        let transport: Arc<dyn AsyncTransport> = match self.async_transport.take() {
+            Some(t) => t,
+            None => Arc::new(AsyncReqwestTransport::from_options(&self.client_options)?),
+        };
+
+        Ok(self.build_async_with_transport(transport))
+    }
+
+    #[cfg(feature = "async")]
+    fn build_async_with_transport(self, transport: Arc<dyn AsyncTransport>) -> AsyncEdupage {
+        AsyncEdupage {
+            is_logged_in: false,
+            data: None,
+            transport,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            cookies: Arc::new(SessionCookies::default()),
+            gsec_hash: GsecHash::default(),
+            reauthentication: None,
+            host: self.host,
+            capture: self.capture,
+            parse_mode: self.parse_mode,
+            parse_warnings: Vec::new(),
+            offline: false,
+            subdomain: None,
+        }
+    }
+
+    /// The async version of [`EdupageBuilder::build_from_snapshot`], see
+    /// [`AsyncEdupage::from_snapshot`].
+    #[cfg(feature = "async")]
+    pub fn build_async_from_snapshot(self, snapshot: &str) -> Result<AsyncEdupage, EdupageError> {
+        let (data, warnings) = parse_snapshot(snapshot, self.parse_mode)?;
+
+        let mut edupage = self.build_async_with_transport(Arc::new(OfflineTransport));
+        edupage.data = Some(data);
+        edupage.parse_warnings = warnings;
+        edupage.is_logged_in = true;
+        edupage.offline = true;
+
+        Ok(edupage)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RequestType {
     GET,
     POST,
 }
 
+/// The session cookies, which can be exported and restored.
+///
+/// [`Edupage`] and `AsyncEdupage` send these with every request themselves.
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Default)]
+pub(crate) struct SessionCookies {
+    jar: RwLock<Jar>,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl SessionCookies {
+    /// Returns the cookies that would be sent with a request to `url`.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn export(&self, url: &Url) -> Vec<SessionCookie> {
+        let header = match self.cookies(url) {
+            Some(h) => h,
+            None => return Vec::new(),
+        };
+
+        let header = match header.to_str() {
+            Ok(h) => h.to_string(),
+            Err(_) => return Vec::new(),
+        };
+
+        header
+            .split("; ")
+            .filter_map(|c| c.split_once('='))
+            .map(|(name, value)| SessionCookie {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect()
+    }
+
+    /// Sets the `Cookie` header of `request` to the cookies for its url.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn add_to(&self, request: &mut HttpRequest) {
+        request
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("cookie"));
+
+        if let Some(cookies) = self.cookies(&request.url) {
+            if let Ok(cookies) = cookies.to_str() {
+                request
+                    .headers
+                    .push(("Cookie".to_string(), cookies.to_string()));
+            }
+        }
+    }
+
+    /// Stores the cookies `response` sets.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn store(&self, response: &HttpResponse) {
+        let set_cookies: Vec<HeaderValue> = response
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("set-cookie"))
+            .filter_map(|(_, value)| HeaderValue::from_str(value).ok())
+            .collect();
+
+        self.set_cookies(&mut set_cookies.iter(), response.url());
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn clear(&self) {
+        if let Ok(mut jar) = self.jar.write() {
+            *jar = Jar::default();
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn import(&self, url: &Url, cookies: &[SessionCookie]) {
+        if let Ok(jar) = self.jar.read() {
+            for cookie in cookies {
+                jar.add_cookie_str(&format!("{}={}; Path=/", cookie.name, cookie.value), url);
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl CookieStore for SessionCookies {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        if let Ok(jar) = self.jar.read() {
+            jar.set_cookies(cookie_headers, url);
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.jar.read().ok()?.cookies(url)
+    }
+}
+
+/// The gsec hash of the session. It can be replaced through a shared reference (when logging in
+/// again), but every clone gets its own copy, like the user's data it belongs to.
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Default)]
+pub(crate) struct GsecHash(RwLock<Option<String>>);
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl GsecHash {
+    fn get(&self) -> Option<String> {
+        self.0.read().ok()?.clone()
+    }
+
+    fn set(&self, gsec_hash: Option<String>) {
+        if let Ok(mut current) = self.0.write() {
+            *current = gsec_hash;
+        }
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl Clone for GsecHash {
+    fn clone(&self) -> Self {
+        Self(RwLock::new(self.get()))
+    }
+}
+
+/// Returns the gsec hash, which is required by some of edupage's endpoints.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn parse_gsec_hash(html: &str) -> Option<String> {
+    html.split("ASC.gsechash=\"")
+        .nth(1)?
+        .split("\"")
+        .next()
+        .map(str::to_string)
+}
+
+/// How many redirects are followed before giving up.
+#[cfg(any(feature = "blocking", feature = "async"))]
+const MAX_REDIRECTS: usize = 10;
+
+/// Edupage redirects to the login page when the session is not valid anymore.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn is_login_redirect(url: &Url) -> bool {
+    url.path().starts_with("/login")
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn build_request(
+    url: String,
+    request_type: RequestType,
+    headers: Option<HashMap<String, String>>,
+    post_data: Option<String>,
+) -> Result<HttpRequest, EdupageError> {
+    let url = Url::parse(&url).map_err(EdupageError::parse)?;
+
+    let body = match request_type {
+        RequestType::GET => None,
+        RequestType::POST => post_data.map(String::into_bytes),
+    };
+
+    Ok(HttpRequest {
+        method: request_type,
+        url,
+        headers: headers.unwrap_or_default().into_iter().collect(),
+        body,
+    })
+}
+
+/// Changes `request` to the request for the page `response` redirects to.
+///
+/// Returns `false` if `response` is not a redirect.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn follow_redirect(
+    request: &mut HttpRequest,
+    response: &HttpResponse,
+) -> Result<bool, EdupageError> {
+    let location = match response.header("location") {
+        Some(location) if response.is_redirect() => location,
+        _ => return Ok(false),
+    };
+
+    request.url = request.url.join(location).map_err(EdupageError::parse)?;
+
+    // like browsers, only 307 and 308 keep the method and the body
+    if !matches!(response.status, 307 | 308) {
+        request.method = RequestType::GET;
+        request.body = None;
+        request
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("content-type"));
+    }
+
+    Ok(true)
+}
+
+/// A redirect to the login page means the session expired, see `session_request`.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_session_response(response: HttpResponse) -> Result<HttpResponse, EdupageError> {
+    if is_login_redirect(response.url()) {
+        return Err(EdupageError::SessionExpired);
+    }
+
+    if response.status >= 400 {
+        return Err(EdupageError::http_status(response.status));
+    }
+
+    Ok(response)
+}
+
+/// Returns the json with the user's data from the userhome page.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn userhome_json(html: &str) -> Result<String, EdupageError> {
+    match html
+        .split("userhome(")
+        .nth(1)
+        .and_then(|x| x.rsplitn(3, ");").nth(2))
+    {
+        Some(x) => Ok(x.replace("\t", "").replace("\n", "").replace("\r", "")),
+        None => Err(EdupageError::ParseError("Bad data!".into())),
+    }
+}
+
+/// Parses a saved userhome page, or just the json with the user's data.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_snapshot(
+    snapshot: &str,
+    parse_mode: ParseMode,
+) -> Result<(UserData, Vec<ParseWarning>), EdupageError> {
+    let json = if snapshot.contains("userhome(") {
+        userhome_json(snapshot)?
+    } else {
+        snapshot.to_string()
+    };
+
+    parse_user_data(&json, parse_mode)
+}
+
+/// The user's data and the gsec hash from the userhome page.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) struct Userhome {
+    pub(crate) data: UserData,
+    pub(crate) warnings: Vec<ParseWarning>,
+    pub(crate) gsec_hash: String,
+}
+
+/// Parses the user's data and the gsec hash from the userhome page.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn parse_userhome(
+    html: &str,
+    capture: Option<&DebugCapture>,
+    parse_mode: ParseMode,
+) -> Result<Userhome, EdupageError> {
+    let json = userhome_json(html)?;
+
+    if let Some(capture) = capture {
+        capture.capture(PayloadKind::Userhome, &json);
+    }
+
+    let (data, warnings) = parse_user_data(&json, parse_mode)?;
+
+    match parse_gsec_hash(html) {
+        None => Err(EdupageError::ParseError("No gsechash in response!".into())),
+        Some(gsec_hash) => Ok(Userhome {
+            data,
+            warnings,
+            gsec_hash,
+        }),
+    }
+}
+
+/// Gives access to the user's data downloaded during login.
+///
+/// Traits that only read this data (like [`crate::traits::DBI`] or [`crate::traits::Timeline`])
+/// are implemented for every type that implements this trait, so they work the same way for
+/// both [`Edupage`] and `AsyncEdupage`.
+pub trait UserDataSource {
+    fn user_data(&self) -> Result<&UserData, EdupageError>;
+}
+
+#[cfg(feature = "blocking")]
+impl UserDataSource for Edupage {
+    fn user_data(&self) -> Result<&UserData, EdupageError> {
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
+        }
+
+        self.data.as_ref().ok_or(EdupageError::NotLoggedIn)
+    }
+}
+
+#[cfg(feature = "blocking")]
 impl Default for Edupage {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "blocking")]
 impl Edupage {
-    pub(crate) fn build_client() -> reqwest::blocking::Client {
-        Client::builder()
-            .connection_verbose(true)
-            .cookie_store(true)
-            .build()
-            .unwrap()
+    pub fn new() -> Self {
+        // the default configuration does not set anything that could make building the client fail
+        EdupageBuilder::new().build().unwrap()
     }
 
-    pub fn new() -> Self {
-        let client = Self::build_client();
+    pub fn builder() -> EdupageBuilder {
+        EdupageBuilder::new()
+    }
 
-        Self {
-            is_logged_in: false,
-            data: None,
-            client,
-            gsec_hash: None,
-            subdomain: None,
+    /// Creates a read-only `Edupage` from a saved userhome page, without logging in.
+    ///
+    /// The snapshot is either the whole html of the userhome page (`https://{subdomain}.edupage.org/user/`)
+    /// or just the json with the user's data, e.g. a `userhome-*.json` file saved by
+    /// [`crate::capture::DebugCapture::directory`].
+    ///
+    /// Everything served from the user's data ([`crate::traits::DBI`], [`crate::traits::Timeline`],
+    /// [`crate::traits::Timetable`], [`crate::traits::Ringing`]) works as usual, everything that
+    /// needs to talk to edupage returns [`EdupageError::Offline`].
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::DBI};
+    ///
+    /// let edupage = Edupage::from_snapshot_file("userhome.html").unwrap();
+    /// println!("{:?}", edupage.get_teachers().unwrap());
+    /// ```
+    pub fn from_snapshot(snapshot: &str) -> Result<Self, EdupageError> {
+        Self::builder().build_from_snapshot(snapshot)
+    }
+
+    /// Same as [`Edupage::from_snapshot`], but reads the snapshot from a file.
+    pub fn from_snapshot_file(path: impl AsRef<Path>) -> Result<Self, EdupageError> {
+        let snapshot = std::fs::read_to_string(path).map_err(EdupageError::other)?;
+
+        Self::from_snapshot(&snapshot)
+    }
+
+    /// The values of the user's data that could not be parsed and were replaced or skipped,
+    /// when parsing with [`ParseMode::Lenient`]. Updated every time the data is loaded
+    /// (when logging in, restoring a session, switching children, ...).
+    pub fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.parse_warnings
+    }
+
+    /// Whether this `Edupage` was created from a snapshot and cannot send any requests.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub(crate) fn ensure_online(&self) -> Result<(), EdupageError> {
+        if self.offline {
+            Err(EdupageError::Offline)
+        } else {
+            Ok(())
         }
     }
+
+    /// Returns the url of `path` on the logged in user's school subdomain,
+    /// e.g. `https://school.edupage.org/user/` for `/user/`.
+    pub fn url(&self, path: &str) -> Result<String, EdupageError> {
+        self.ensure_online()?;
+
+        match &self.subdomain {
+            Some(subdomain) => Ok(self.host.url(subdomain, path)),
+            None => Err(EdupageError::NotLoggedIn),
+        }
+    }
+
     /// This method can be used for making authenticated requests to edupage.
     ///
     /// Example usage (sending a message manually):
@@ -72,7 +871,7 @@ impl Edupage {
     ///
     /// edupage.login("subdomain", "username", "password").unwrap();
     ///
-    /// let url = format!("https://{}.edupage.org/timeline/?akcia=createItem", edupage.subdomain.clone().unwrap());
+    /// let url = edupage.url("/timeline/?akcia=createItem").unwrap();
     /// let request_data = "{\"selectedUser\": \"Student12345\", \"text\": \"Hello World!\", \"attachements\": {}, \"receipt\": 0, \"typ\": \"sprava\"}";
     /// let response = edupage.request(
     ///     url,
@@ -90,74 +889,299 @@ impl Edupage {
         request_type: RequestType,
         headers: Option<HashMap<String, String>>,
         post_data: Option<String>,
-    ) -> Result<Response, String> {
-        let header_map = if let Some(hd) = headers {
-            let mut header_map: HeaderMap = HeaderMap::new();
-
-            for (k, v) in hd {
-                header_map.insert(
-                    HeaderName::from_bytes(k.as_bytes()).unwrap(),
-                    HeaderValue::from_str(&v).unwrap(),
-                );
+    ) -> Result<HttpResponse, EdupageError> {
+        self.send(build_request(url, request_type, headers, post_data)?)
+    }
+
+    /// Sends the request through the transport, attaching the session cookies and following
+    /// redirects.
+    pub(crate) fn send(&self, mut request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        self.ensure_online()?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.cookies.add_to(&mut request);
+
+            let response = self.send_with_retries(&request)?;
+            self.cookies.store(&response);
+
+            if !follow_redirect(&mut request, &response)? {
+                return Ok(response);
             }
+        }
 
-            header_map
-        } else {
-            HeaderMap::new()
-        };
+        Err(EdupageError::HTTPError("Too many redirects!".into()))
+    }
 
-        let response = match request_type {
-            RequestType::GET => self.client.get(url).headers(header_map),
-            RequestType::POST => {
-                let mut request_builder = self.client.post(url).headers(header_map);
+    /// Sends a single request through the transport, retrying it according to the retry policy.
+    fn send_with_retries(&self, request: &HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let max_retries = self.retry_policy.retries_for(request.method);
+        let mut attempt = 0;
 
-                if let Some(pd) = post_data {
-                    request_builder = request_builder.body(pd);
-                }
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire();
+            }
 
-                request_builder
+            let result = self.transport.send(request.clone());
+
+            if attempt >= max_retries || !self.retry_policy.is_retryable_result(&result) {
+                return result;
             }
+
+            thread::sleep(self.retry_policy.backoff(attempt, result.as_ref().ok()));
+            attempt += 1;
+        }
+    }
+
+    pub(crate) fn capture(&self, kind: PayloadKind, content: &str) {
+        if let Some(capture) = &self.capture {
+            capture.capture(kind, content);
+        }
+    }
+
+    pub(crate) fn parse_login_data(&mut self, html: String) -> Result<(), EdupageError> {
+        let userhome = parse_userhome(&html, self.capture.as_ref(), self.parse_mode)?;
+
+        self.data = Some(userhome.data);
+        self.parse_warnings = userhome.warnings;
+        self.set_gsec_hash(Some(userhome.gsec_hash));
+
+        Ok(())
+    }
+
+    /// Same as [`Edupage::request`], but a redirect to the login page is reported as
+    /// [`EdupageError::SessionExpired`].
+    pub(crate) fn session_request(
+        &self,
+        url: String,
+        request_type: RequestType,
+        headers: Option<HashMap<String, String>>,
+        post_data: Option<String>,
+    ) -> Result<HttpResponse, EdupageError> {
+        self.ensure_online()?;
+
+        check_session_response(self.request(url, request_type, headers, post_data)?)
+    }
+
+    pub(crate) fn gsec_hash(&self) -> Option<String> {
+        self.gsec_hash.get()
+    }
+
+    pub(crate) fn set_gsec_hash(&self, gsec_hash: Option<String>) {
+        self.gsec_hash.set(gsec_hash);
+    }
+
+    pub fn logged_in(&self) -> bool {
+        self.is_logged_in
+    }
+}
+
+/// The async version of [`Edupage`], which uses a non-blocking http client.
+///
+/// The traits that only read the downloaded data (like [`crate::traits::DBI`]) work the same way
+/// as for [`Edupage`], the async versions of the other traits are prefixed with `Async`
+/// (e.g. [`crate::traits::AsyncLogin`]).
+///
+/// The requests are sent through an [`AsyncTransport`] with the same handling of cookies,
+/// redirects, retries ([`RetryPolicy`]) and rate limits ([`RateLimiter`]) as [`Edupage`].
+///
+/// Clones share the session cookies and copy everything else, the same way as clones of
+/// [`Edupage`] do.
+///
+/// Example usage:
+/// ```no_run
+/// use cdupage::{edupage::AsyncEdupage, traits::{AsyncLogin, DBI}};
+///
+/// async fn print_teachers() {
+///     let mut edupage = AsyncEdupage::new();
+///     edupage.login("subdomain", "username", "password").await.unwrap();
+///
+///     println!("{:?}", edupage.get_teachers());
+/// }
+/// ```
+#[cfg(feature = "async")]
+#[derive(Clone)]
+pub struct AsyncEdupage {
+    pub(crate) is_logged_in: bool,
+    pub(crate) transport: Arc<dyn AsyncTransport>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) cookies: Arc<SessionCookies>,
+    pub(crate) data: Option<UserData>,
+    pub(crate) gsec_hash: GsecHash,
+    pub(crate) reauthentication: Option<CredentialSource>,
+    pub(crate) host: EdupageHost,
+    pub(crate) capture: Option<DebugCapture>,
+    pub(crate) parse_mode: ParseMode,
+    pub(crate) parse_warnings: Vec<ParseWarning>,
+    pub(crate) offline: bool,
+    pub subdomain: Option<String>,
+}
+
+#[cfg(feature = "async")]
+impl UserDataSource for AsyncEdupage {
+    fn user_data(&self) -> Result<&UserData, EdupageError> {
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
+        }
+
+        self.data.as_ref().ok_or(EdupageError::NotLoggedIn)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Default for AsyncEdupage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncEdupage {
+    pub fn new() -> Self {
+        // the default configuration does not set anything that could make building the client fail
+        EdupageBuilder::new().build_async().unwrap()
+    }
+
+    pub fn builder() -> EdupageBuilder {
+        EdupageBuilder::new()
+    }
+
+    /// Creates a read-only `AsyncEdupage` from a saved userhome page, without logging in.
+    ///
+    /// See [`Edupage::from_snapshot`].
+    pub fn from_snapshot(snapshot: &str) -> Result<Self, EdupageError> {
+        Self::builder().build_async_from_snapshot(snapshot)
+    }
+
+    /// Same as [`AsyncEdupage::from_snapshot`], but reads the snapshot from a file.
+    pub fn from_snapshot_file(path: impl AsRef<Path>) -> Result<Self, EdupageError> {
+        let snapshot = std::fs::read_to_string(path).map_err(EdupageError::other)?;
+
+        Self::from_snapshot(&snapshot)
+    }
+
+    /// See [`Edupage::parse_warnings`].
+    pub fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.parse_warnings
+    }
+
+    /// Whether this `AsyncEdupage` was created from a snapshot and cannot send any requests.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub(crate) fn ensure_online(&self) -> Result<(), EdupageError> {
+        if self.offline {
+            Err(EdupageError::Offline)
+        } else {
+            Ok(())
         }
-        .send();
+    }
 
-        match response {
-            Ok(x) => Ok(x),
-            Err(e) => Err(e.to_string()),
+    /// Returns the url of `path` on the logged in user's school subdomain,
+    /// e.g. `https://school.edupage.org/user/` for `/user/`.
+    pub fn url(&self, path: &str) -> Result<String, EdupageError> {
+        self.ensure_online()?;
+
+        match &self.subdomain {
+            Some(subdomain) => Ok(self.host.url(subdomain, path)),
+            None => Err(EdupageError::NotLoggedIn),
         }
     }
 
-    pub(crate) fn parse_login_data(&mut self, html: String) -> Result<(), String> {
-        let json = match html.split("userhome(").nth(1) {
-            Some(x) => x,
-            None => return Err("Bad data!".to_string()),
+    /// The async version of [`Edupage::request`].
+    pub async fn request(
+        &self,
+        url: String,
+        request_type: RequestType,
+        headers: Option<HashMap<String, String>>,
+        post_data: Option<String>,
+    ) -> Result<HttpResponse, EdupageError> {
+        self.send(build_request(url, request_type, headers, post_data)?)
+            .await
+    }
+
+    /// The async version of [`Edupage::send`].
+    pub(crate) async fn send(
+        &self,
+        mut request: HttpRequest,
+    ) -> Result<HttpResponse, EdupageError> {
+        self.ensure_online()?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.cookies.add_to(&mut request);
+
+            let response = self.send_with_retries(&request).await?;
+            self.cookies.store(&response);
+
+            if !follow_redirect(&mut request, &response)? {
+                return Ok(response);
+            }
         }
-        .rsplitn(3, ");")
-        .nth(2)
-        .unwrap()
-        .replace("\t", "")
-        .replace("\n", "")
-        .replace("\r", "");
 
-        File::create("dump.json")
-            .unwrap()
-            .write_all(json.as_bytes())
-            .unwrap();
+        Err(EdupageError::HTTPError("Too many redirects!".into()))
+    }
 
-        self.data = Some(match serde_json::from_str(&json) {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(e.to_string());
+    /// Sends a single request through the transport, retrying it according to the retry policy.
+    async fn send_with_retries(&self, request: &HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let max_retries = self.retry_policy.retries_for(request.method);
+        let mut attempt = 0;
+
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire_async().await;
             }
-        });
 
-        self.gsec_hash = match html.split("ASC.gsechash=\"").nth(1) {
-            None => return Err("No gsechash in response!".to_string()),
-            Some(v) => Some(v.split("\"").nth(0).unwrap().to_string()),
-        };
+            let result = self.transport.send(request.clone()).await;
+
+            if attempt >= max_retries || !self.retry_policy.is_retryable_result(&result) {
+                return result;
+            }
+
+            tokio::time::sleep(self.retry_policy.backoff(attempt, result.as_ref().ok())).await;
+            attempt += 1;
+        }
+    }
+
+    pub(crate) fn capture(&self, kind: PayloadKind, content: &str) {
+        if let Some(capture) = &self.capture {
+            capture.capture(kind, content);
+        }
+    }
+
+    pub(crate) fn parse_login_data(&mut self, html: String) -> Result<(), EdupageError> {
+        let userhome = parse_userhome(&html, self.capture.as_ref(), self.parse_mode)?;
+
+        self.data = Some(userhome.data);
+        self.parse_warnings = userhome.warnings;
+        self.set_gsec_hash(Some(userhome.gsec_hash));
 
         Ok(())
     }
 
+    /// Same as [`AsyncEdupage::request`], but a redirect to the login page is reported as
+    /// [`EdupageError::SessionExpired`].
+    pub(crate) async fn session_request(
+        &self,
+        url: String,
+        request_type: RequestType,
+        headers: Option<HashMap<String, String>>,
+        post_data: Option<String>,
+    ) -> Result<HttpResponse, EdupageError> {
+        self.ensure_online()?;
+
+        check_session_response(self.request(url, request_type, headers, post_data).await?)
+    }
+
+    pub(crate) fn gsec_hash(&self) -> Option<String> {
+        self.gsec_hash.get()
+    }
+
+    pub(crate) fn set_gsec_hash(&self, gsec_hash: Option<String>) {
+        self.gsec_hash.set(gsec_hash);
+    }
+
     pub fn logged_in(&self) -> bool {
         self.is_logged_in
     }
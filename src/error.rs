@@ -0,0 +1,309 @@
+//! The error type of the crate.
+use std::{error::Error, fmt, io, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+/// Describes what went wrong, with the error that caused it (if any) and the HTTP status code
+/// of the response (if edupage answered).
+///
+/// The source is not serialized, only its message is kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    message: String,
+    status: Option<u16>,
+    #[serde(skip)]
+    source: Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl ErrorDetails {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+            source: None,
+        }
+    }
+
+    /// Uses the message of `error` and keeps `error` as the source.
+    pub fn from_error(error: impl Error + Send + Sync + 'static) -> Self {
+        Self {
+            message: error.to_string(),
+            status: None,
+            source: Some(Arc::new(error)),
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+}
+
+impl From<String> for ErrorDetails {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ErrorDetails {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl fmt::Display for ErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Serialized as `{ "code": ..., "message": ..., "status": ... }`, see [`EdupageError::code`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "SerializedError", from = "SerializedError")]
+pub enum EdupageError {
+    InvalidCredentials,
+    SessionExpired,
+    CaptchaRequired,
+    TwoFactorRequired,
+    /// The request could not be sent, or edupage answered with an error status code
+    /// (see [`EdupageError::status`]).
+    HTTPError(ErrorDetails),
+    InvalidResponse,
+    /// Edupage sent something we do not understand.
+    ParseError(ErrorDetails),
+    /// Serializing or deserializing json failed.
+    SerializationError(ErrorDetails),
+    NotLoggedIn,
+    MissingData,
+    /// The `Edupage` was created from a snapshot with
+    /// [`crate::edupage::Edupage::from_snapshot`], so nothing can be sent to edupage.
+    Offline,
+    /// Edupage replied to an RPC call with an error.
+    RpcError(String),
+    Other(ErrorDetails),
+}
+
+impl EdupageError {
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn http(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::HTTPError(ErrorDetails::from_error(error))
+    }
+
+    /// Edupage answered with an error status code.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn http_status(status: u16) -> Self {
+        Self::HTTPError(
+            ErrorDetails::new(format!("Edupage responded with status {}", status))
+                .with_status(status),
+        )
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn parse(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::ParseError(ErrorDetails::from_error(error))
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn serialization(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::SerializationError(ErrorDetails::from_error(error))
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn other(error: impl Error + Send + Sync + 'static) -> Self {
+        Self::Other(ErrorDetails::from_error(error))
+    }
+
+    /// A stable code for the kind of the error, e.g. `session_expired`.
+    ///
+    /// The codes do not change between versions, unlike the messages, so they are safe to match
+    /// on (e.g. in the node bindings, where the error is an object with `code`, `message` and
+    /// `status`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            EdupageError::InvalidCredentials => "invalid_credentials",
+            EdupageError::SessionExpired => "session_expired",
+            EdupageError::CaptchaRequired => "captcha_required",
+            EdupageError::TwoFactorRequired => "two_factor_required",
+            EdupageError::HTTPError(_) => "http_error",
+            EdupageError::InvalidResponse => "invalid_response",
+            EdupageError::ParseError(_) => "parse_error",
+            EdupageError::SerializationError(_) => "serialization_error",
+            EdupageError::NotLoggedIn => "not_logged_in",
+            EdupageError::MissingData => "missing_data",
+            EdupageError::Offline => "offline",
+            EdupageError::RpcError(_) => "rpc_error",
+            EdupageError::Other(_) => "other",
+        }
+    }
+
+    fn details(&self) -> Option<&ErrorDetails> {
+        match self {
+            EdupageError::HTTPError(details)
+            | EdupageError::ParseError(details)
+            | EdupageError::SerializationError(details)
+            | EdupageError::Other(details) => Some(details),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code of edupage's response, if the error was caused by one.
+    pub fn status(&self) -> Option<u16> {
+        self.details().and_then(ErrorDetails::status)
+    }
+
+    /// Whether sending the same request again later could succeed: the connection could not be
+    /// made (e.g. it was refused or reset), the request timed out, rate limiting (429) and server
+    /// errors (5xx).
+    ///
+    /// Other errors of the connection (like an invalid certificate) or of the client (like too
+    /// many redirects) are not retryable, they would happen again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            EdupageError::HTTPError(details) => match (details.status, &details.source) {
+                (Some(status), _) => status == 429 || (500..600).contains(&status),
+                (None, Some(source)) => is_transient(source.as_ref()),
+                (None, None) => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the error is about the login or the session, i.e. logging in (again) is needed.
+    pub fn is_auth(&self) -> bool {
+        matches!(
+            self,
+            EdupageError::InvalidCredentials
+                | EdupageError::SessionExpired
+                | EdupageError::CaptchaRequired
+                | EdupageError::TwoFactorRequired
+                | EdupageError::NotLoggedIn
+        )
+    }
+}
+
+/// Whether the error (or one of its sources) is a timeout or a connection error which can go away
+/// by itself.
+fn is_transient(error: &(dyn Error + 'static)) -> bool {
+    let mut current = Some(error);
+
+    while let Some(error) = current {
+        if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            if error.is_timeout() {
+                return true;
+            }
+        }
+
+        if let Some(error) = error.downcast_ref::<io::Error>() {
+            return matches!(
+                error.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::HostUnreachable
+                    | io::ErrorKind::NetworkUnreachable
+                    | io::ErrorKind::NetworkDown
+            );
+        }
+
+        current = error.source();
+    }
+
+    false
+}
+
+impl fmt::Display for EdupageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdupageError::InvalidCredentials => f.write_str("invalid credentials"),
+            EdupageError::SessionExpired => f.write_str("the session expired"),
+            EdupageError::CaptchaRequired => f.write_str("edupage requires a captcha"),
+            EdupageError::TwoFactorRequired => {
+                f.write_str("the account requires 2 factor authentication")
+            }
+            EdupageError::HTTPError(details) => write!(f, "http error: {}", details),
+            EdupageError::InvalidResponse => f.write_str("invalid response from edupage"),
+            EdupageError::ParseError(details) => write!(f, "parse error: {}", details),
+            EdupageError::SerializationError(details) => {
+                write!(f, "serialization error: {}", details)
+            }
+            EdupageError::NotLoggedIn => f.write_str("not logged in"),
+            EdupageError::MissingData => f.write_str("the data is missing"),
+            EdupageError::Offline => {
+                f.write_str("this Edupage was created from a snapshot and cannot send requests")
+            }
+            EdupageError::RpcError(message) => write!(f, "rpc error: {}", message),
+            EdupageError::Other(details) => details.fmt(f),
+        }
+    }
+}
+
+impl Error for EdupageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        let source = self.details()?.source.as_ref()?;
+
+        Some(source.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedError {
+    code: String,
+    message: String,
+    status: Option<u16>,
+}
+
+impl From<EdupageError> for SerializedError {
+    fn from(error: EdupageError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+            status: error.status(),
+        }
+    }
+}
+
+impl From<SerializedError> for EdupageError {
+    fn from(error: SerializedError) -> Self {
+        // the message is the one from `Display`, so the prefix is added again when displayed
+        let details = |prefix: &str| ErrorDetails {
+            message: error
+                .message
+                .strip_prefix(prefix)
+                .unwrap_or(&error.message)
+                .to_string(),
+            status: error.status,
+            source: None,
+        };
+
+        match error.code.as_str() {
+            "invalid_credentials" => EdupageError::InvalidCredentials,
+            "session_expired" => EdupageError::SessionExpired,
+            "captcha_required" => EdupageError::CaptchaRequired,
+            "two_factor_required" => EdupageError::TwoFactorRequired,
+            "http_error" => EdupageError::HTTPError(details("http error: ")),
+            "invalid_response" => EdupageError::InvalidResponse,
+            "parse_error" => EdupageError::ParseError(details("parse error: ")),
+            "serialization_error" => {
+                EdupageError::SerializationError(details("serialization error: "))
+            }
+            "not_logged_in" => EdupageError::NotLoggedIn,
+            "missing_data" => EdupageError::MissingData,
+            "offline" => EdupageError::Offline,
+            "rpc_error" => EdupageError::RpcError(details("rpc error: ").message),
+            // e.g. a code added in a newer version
+            _ => EdupageError::Other(details("")),
+        }
+    }
+}
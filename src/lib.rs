@@ -4,8 +4,13 @@
 pub(crate) mod deserializers;
 pub(crate) mod macro_aliases;
 
+pub mod capture;
 pub mod edupage;
+pub mod error;
+pub mod parsing;
 pub mod traits;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub mod transport;
 pub mod types;
 
 #[cfg(feature = "node")]
@@ -14,79 +19,527 @@ mod node;
 #[macro_use]
 extern crate macro_rules_attribute;
 
-#[cfg(test)]
+#[cfg(all(test, any(feature = "blocking", feature = "async")))]
 #[macro_use]
 extern crate assert_matches;
 
-#[cfg(not(feature = "node-types"))]
+#[cfg(all(feature = "blocking", not(feature = "node-types")))]
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use chrono::NaiveDate;
+    use reqwest::Url;
 
-    use crate::traits::{Login, Timeline, Timetable, DBI};
+    use crate::capture::{DebugCapture, PayloadKind};
+    use crate::edupage::{Edupage, EdupageError, RequestType, UserDataSource};
+    use crate::parsing::{ParseAction, ParseMode, ParseWarning};
+    use crate::traits::{
+        Children, Cloud, CredentialSource, Credentials, Login, LoginState, Portal, Profile,
+        Reauthentication, Refresh, Rpc, Session, Substitution, Timeline, Timetable,
+        UserDataSection, DBI,
+    };
+    use crate::transport::{
+        HttpRequest, HttpResponse, RateLimiter, RecordingTransport, ReplayTransport,
+        ReqwestTransport, RetryPolicy, Transport,
+    };
+    use crate::types::{
+        Capabilities, DBIEntityKind, MatchKind, PortalLoginState, SessionSnapshot, UserProfile,
+    };
 
     fn get_env_var(name: &'static str) -> Option<String> {
         use std::env;
 
-        match env::var(name) {
-            Ok(v) => Some(v),
-            Err(_) => None,
+        env::var(name).ok()
+    }
+
+    fn fixture_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    /// An `Edupage` answering from a cassette that can not be recorded again (e.g. one that needs
+    /// an account with 2 factor authentication).
+    fn replay_edupage(cassette: &str) -> Edupage {
+        let cassette = fixture_path("cassettes").join(format!("{}.json", cassette));
+
+        Edupage::builder()
+            .transport(ReplayTransport::load(cassette).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    /// Same as [`replay_edupage`], but the requests are recorded too, so the tests can check
+    /// what was sent.
+    fn recorded_replay_edupage(cassette: &str) -> (Edupage, RecordingTransport<ReplayTransport>) {
+        let cassette = fixture_path("cassettes").join(format!("{}.json", cassette));
+        let recorder = RecordingTransport::new(ReplayTransport::load(cassette).unwrap());
+
+        let edupage = Edupage::builder()
+            .transport(recorder.clone())
+            .build()
+            .unwrap();
+
+        (edupage, recorder)
+    }
+
+    /// An `Edupage` for the tests.
+    ///
+    /// By default, the requests are answered from `fixtures/cassettes/{cassette}.json`, so the
+    /// tests run offline. When `EDUPAGE_RECORD` is set, the real edupage is used (with the
+    /// `SUBDOMAIN`, `USERNAME` and `PASSWORD` environment variables) and the cassette is recorded
+    /// again, with the credentials scrubbed.
+    struct TestEdupage {
+        edupage: Edupage,
+        subdomain: String,
+        username: String,
+        password: String,
+        recorder: Option<(RecordingTransport<ReqwestTransport>, PathBuf)>,
+    }
+
+    impl TestEdupage {
+        const SUBDOMAIN: &'static str = "demo";
+        const USERNAME: &'static str = "user";
+        const PASSWORD: &'static str = "password";
+
+        fn new(cassette: &str) -> Self {
+            dotenv::dotenv().ok();
+
+            let cassette = fixture_path("cassettes").join(format!("{}.json", cassette));
+
+            if get_env_var("EDUPAGE_RECORD").is_none() {
+                let transport = ReplayTransport::load(&cassette).unwrap();
+
+                return Self {
+                    edupage: Edupage::builder().transport(transport).build().unwrap(),
+                    subdomain: Self::SUBDOMAIN.to_string(),
+                    username: Self::USERNAME.to_string(),
+                    password: Self::PASSWORD.to_string(),
+                    recorder: None,
+                };
+            }
+
+            let subdomain = get_env_var("SUBDOMAIN");
+            let username = get_env_var("USERNAME");
+            let password = get_env_var("PASSWORD");
+
+            if [&subdomain, &username, &password].contains(&&None) {
+                debug_assert_ne!(subdomain, None);
+                debug_assert_ne!(username, None);
+                debug_assert_ne!(password, None);
+            }
+
+            let subdomain = subdomain.unwrap();
+            let username = username.unwrap();
+            let password = password.unwrap();
+
+            let recorder = RecordingTransport::new(ReqwestTransport::new().unwrap())
+                .scrub(&password, Self::PASSWORD)
+                .scrub(&username, Self::USERNAME)
+                .scrub(&subdomain, Self::SUBDOMAIN);
+
+            Self {
+                edupage: Edupage::builder()
+                    .transport(recorder.clone())
+                    .build()
+                    .unwrap(),
+                subdomain,
+                username,
+                password,
+                recorder: Some((recorder, cassette)),
+            }
+        }
+
+        fn login(&mut self) {
+            let login_result = self
+                .edupage
+                .login(&self.subdomain, &self.username, &self.password);
+
+            assert_matches!(login_result, Ok(_));
+        }
+
+        /// Saves the cassette when recording.
+        fn finish(self) {
+            if let Some((recorder, cassette)) = self.recorder {
+                recorder.save(cassette).unwrap();
+            }
         }
     }
 
     #[test]
     fn login_test() {
-        dotenv::dotenv().ok();
+        let mut test = TestEdupage::new("login");
+        test.login();
 
-        use crate::edupage::Edupage;
+        assert!(test.edupage.logged_in());
 
-        let subdomain = get_env_var("SUBDOMAIN");
-        let username = get_env_var("USERNAME");
-        let password = get_env_var("PASSWORD");
+        test.finish();
+    }
 
-        if [&subdomain, &username, &password].contains(&&None) {
-            debug_assert_ne!(subdomain, None);
-            debug_assert_ne!(username, None);
-            debug_assert_ne!(password, None);
-        }
+    #[test]
+    fn portal_test() {
+        let mut edupage = replay_edupage("portal");
+
+        let accounts = match edupage.portal_login(TestEdupage::USERNAME, TestEdupage::PASSWORD) {
+            Ok(PortalLoginState::AccountSelection(accounts)) => accounts,
+            state => panic!("expected the accounts to choose from, got {:?}", state),
+        };
+        assert_eq!(accounts.len(), 2);
+        assert!(!edupage.logged_in());
+
+        // the login link of the other school expired, edupage sends us to its login page
+        assert_matches!(
+            edupage.open_portal_account(&accounts[1]),
+            Err(EdupageError::InvalidResponse)
+        );
+        assert!(!edupage.logged_in());
+
+        assert_matches!(edupage.open_portal_account(&accounts[0]), Ok(()));
+        assert!(edupage.logged_in());
+        assert_eq!(edupage.subdomain.as_deref(), Some(TestEdupage::SUBDOMAIN));
+    }
+
+    #[test]
+    fn logout_test() {
+        let mut edupage = replay_edupage("logout");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        let url = Url::parse("https://demo.edupage.org/").unwrap();
+        assert!(!edupage.cookies.export(&url).is_empty());
 
-        let mut edupage = Edupage::new();
+        assert_matches!(edupage.logout(), Ok(()));
+        assert!(!edupage.logged_in());
+        assert!(edupage.cookies.export(&url).is_empty());
+        assert_eq!(edupage.subdomain, None);
+        assert_matches!(edupage.get_teachers(), Err(EdupageError::NotLoggedIn));
 
-        let subdomain = subdomain.unwrap();
-        let username = username.unwrap();
-        let password = password.unwrap();
+        // edupage can not be reached, the local session is cleared anyway
+        let mut edupage = replay_edupage("login");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
 
-        let login_result = edupage.login(&subdomain, &username, &password);
+        assert!(edupage.logout().is_err());
+        assert!(!edupage.logged_in());
+        assert!(edupage.cookies.export(&url).is_empty());
+        assert_eq!(edupage.subdomain, None);
 
-        assert_matches!(login_result, Ok(_));
+        // edupage answers with an error, the local session is cleared anyway
+        let mut edupage = replay_edupage("logout_failed");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
 
+        assert_matches!(
+            edupage.logout(),
+            Err(EdupageError::HTTPError(ref details)) if details.status() == Some(500)
+        );
+        assert!(!edupage.logged_in());
+        assert!(edupage.cookies.export(&url).is_empty());
+    }
+
+    #[test]
+    fn builder_test() {
+        let edupage = Edupage::builder()
+            .timeout(Duration::from_secs(120))
+            .connect_timeout(Duration::from_secs(10))
+            .read_timeout(Duration::from_secs(60))
+            .proxy("http://proxy.example.com:3128")
+            .user_agent("cdupage-tests/1.0")
+            .verbose(true)
+            .build()
+            .unwrap();
+        assert!(!edupage.logged_in());
+
+        assert!(Edupage::builder().proxy("not a proxy").build().is_err());
+        assert!(Edupage::builder()
+            .add_root_certificate(b"not a certificate")
+            .build()
+            .is_err());
+
+        // snapshots never connect, so the options of the http client are not even checked
+        let snapshot = std::fs::read_to_string(fixture_path("userhome.html")).unwrap();
+        let edupage = Edupage::builder()
+            .proxy("not a proxy")
+            .build_from_snapshot(&snapshot)
+            .unwrap();
+        assert_matches!(edupage.get_teachers(), Ok(ref teachers) if !teachers.is_empty());
+        assert_matches!(edupage.url("/user/"), Err(EdupageError::Offline));
+    }
+
+    #[test]
+    fn two_factor_test() {
+        let mut edupage = replay_edupage("two_factor");
+
+        let state = edupage.start_login(
+            TestEdupage::SUBDOMAIN,
+            TestEdupage::USERNAME,
+            TestEdupage::PASSWORD,
+        );
+        let challenge = match state {
+            Ok(LoginState::TwoFactorRequired(challenge)) => challenge,
+            state => panic!("expected a 2 factor challenge, got {:?}", state),
+        };
+        assert_eq!(challenge.subdomain, TestEdupage::SUBDOMAIN);
+        assert!(!edupage.logged_in());
+
+        assert_matches!(
+            edupage.finish_login_with_code(&challenge, "000000"),
+            Err(EdupageError::InvalidCredentials)
+        );
+        assert!(!edupage.logged_in());
+
+        assert_matches!(edupage.finish_login_with_code(&challenge, "123456"), Ok(()));
         assert!(edupage.logged_in());
     }
 
     #[test]
-    fn dbi_test() {
-        dotenv::dotenv().ok();
+    fn session_test() {
+        let (mut edupage, recorder) = recorded_replay_edupage("session");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
 
-        use crate::edupage::Edupage;
+        let snapshot = serde_json::to_string(&edupage.export_session().unwrap()).unwrap();
+        let snapshot: SessionSnapshot = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(snapshot.subdomain, TestEdupage::SUBDOMAIN);
+        assert_eq!(snapshot.gsec_hash.as_deref(), Some("0a1b2c3d"));
+        assert!(!snapshot.cookies.is_empty());
 
-        let subdomain = get_env_var("SUBDOMAIN");
-        let username = get_env_var("USERNAME");
-        let password = get_env_var("PASSWORD");
+        // clones get their own copy of the gsec hash
+        let clone = edupage.clone();
+        clone.set_gsec_hash(Some("5e6f7a8b".to_string()));
+        assert_eq!(edupage.gsec_hash().as_deref(), Some("0a1b2c3d"));
 
-        if [&subdomain, &username, &password].contains(&&None) {
-            debug_assert_ne!(subdomain, None);
-            debug_assert_ne!(username, None);
-            debug_assert_ne!(password, None);
-        }
+        // the configuration of the builder is kept
+        let restored = Edupage::builder()
+            .transport(recorder.clone())
+            .parse_mode(ParseMode::Lenient)
+            .restore_session(&snapshot)
+            .unwrap();
+        assert!(restored.logged_in());
+        assert_eq!(restored.parse_mode, ParseMode::Lenient);
+        assert_eq!(restored.subdomain.as_deref(), Some(TestEdupage::SUBDOMAIN));
+        assert_matches!(restored.get_teachers(), Ok(ref teachers) if teachers.len() == 2);
+
+        // edupage redirects to the login page, the session is not valid anymore
+        let expired = Edupage::builder()
+            .transport(recorder)
+            .restore_session(&snapshot);
+        assert_matches!(expired.map(|_| ()), Err(EdupageError::SessionExpired));
+    }
 
-        let mut edupage = Edupage::new();
+    #[test]
+    fn children_test() {
+        let mut test = TestEdupage::new("children");
+        test.login();
+
+        let children = test.edupage.get_children();
+        assert_matches!(children, Ok(ref children) if children.len() == 1 && children[0].id == Some(1234));
+        assert_matches!(test.edupage.get_active_child(), Ok(None));
+
+        // Bob is not Carol's child
+        assert_matches!(
+            test.edupage.switch_to_child(1235),
+            Err(EdupageError::MissingData)
+        );
+
+        assert_matches!(test.edupage.switch_to_child(1234), Ok(()));
+        assert_matches!(test.edupage.get_active_child(), Ok(Some(ref child)) if child.first_name == "Alice");
+
+        test.finish();
+    }
+
+    #[test]
+    fn custom_host_test() {
+        let cassette = fixture_path("cassettes").join("custom_host.json");
+        let recorder = RecordingTransport::new(ReplayTransport::load(cassette).unwrap());
 
-        let subdomain = subdomain.unwrap();
-        let username = username.unwrap();
-        let password = password.unwrap();
+        let mut edupage = Edupage::builder()
+            .transport(recorder.clone())
+            .scheme("http")
+            .domain("edupage.test")
+            .port(8080)
+            .build()
+            .unwrap();
 
-        let login_result = edupage.login(&subdomain, &username, &password);
-        assert_matches!(login_result, Ok(_));
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        assert_matches!(edupage.url("/user/"), Ok(ref url) if url == "http://demo.edupage.test:8080/user/");
+
+        let interactions = recorder.cassette().interactions;
+        assert_eq!(interactions.len(), 3);
+        assert!(interactions
+            .iter()
+            .all(|i| i.request.url.starts_with("http://demo.edupage.test:8080/")));
+    }
+
+    #[test]
+    fn reauthenticate_child_test() {
+        let (mut edupage, recorder) = recorded_replay_edupage("reauthenticate_child");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        edupage.switch_to_child(1234).unwrap();
+        edupage.enable_reauthentication(CredentialSource::Stored(Credentials::new(
+            TestEdupage::SUBDOMAIN.to_string(),
+            TestEdupage::USERNAME.to_string(),
+            TestEdupage::PASSWORD.to_string(),
+        )));
+
+        // the session expires, the new one has to show Alice's data again
+        assert_matches!(edupage.refresh(), Ok(_));
+        assert_matches!(edupage.get_active_child(), Ok(Some(ref child)) if child.first_name == "Alice");
+
+        let switches = recorder
+            .cassette()
+            .interactions
+            .iter()
+            .filter(|i| i.request.url.contains("/login/switchchild?studentid=1234"))
+            .count();
+        assert_eq!(switches, 2);
+    }
+
+    #[test]
+    fn debug_capture_test() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+
+        let capture = DebugCapture::callback(move |payload| {
+            sink.lock().unwrap().push(payload.clone());
+        })
+        .redact(true);
+
+        let transport =
+            ReplayTransport::load(fixture_path("cassettes").join("login.json")).unwrap();
+        let mut edupage = Edupage::builder()
+            .transport(transport)
+            .debug_capture(capture)
+            .build()
+            .unwrap();
+
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].kind, PayloadKind::Userhome);
+        assert!(!captured[0].content.contains("Alice"));
+        assert!(!captured[0].content.contains("Student1234"));
+    }
+
+    #[test]
+    fn redaction_test() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+
+        let capture = DebugCapture::callback(move |payload| {
+            sink.lock().unwrap().push(payload.content.clone());
+        })
+        .redact(true);
+
+        let payload = serde_json::json!({
+            "items": [{
+                "user": "Student1234",
+                "paid": "1234",
+                "data": "{\"studentid\":\"1234\",\"user_meno\":\"Alice Student\"}",
+            }],
+        });
+        capture.capture(PayloadKind::Userhome, &payload.to_string());
+
+        let captured = captured.lock().unwrap();
+        let redacted: serde_json::Value = serde_json::from_str(&captured[0]).unwrap();
+        let item = &redacted["items"][0];
+
+        assert_eq!(item["user"], "Student1");
+        assert_eq!(item["paid"], "1234");
+
+        let data: serde_json::Value = serde_json::from_str(item["data"].as_str().unwrap()).unwrap();
+        assert_eq!(data["studentid"], "1");
+        assert_eq!(data["user_meno"], "REDACTED");
+    }
+
+    #[test]
+    fn timeline_redaction_test() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+
+        let capture = DebugCapture::callback(move |payload| {
+            sink.lock().unwrap().push(payload.content.clone());
+        })
+        .redact(true);
+
+        let payload = serde_json::json!({
+            "items": [{
+                "timelineid": 42,
+                "typ": "homework",
+                "vlastnik": "Teacher12",
+                "vlastnik_meno": "John Teacher",
+                "name": "Mathematics",
+                "data": "{\"studentid\":1234,\"ucitelMeno\":\"John Teacher\",\"student_name\":\"Alice Student\"}",
+            }],
+        });
+        capture.capture(PayloadKind::Userhome, &payload.to_string());
+
+        let captured = captured.lock().unwrap();
+        let redacted: serde_json::Value = serde_json::from_str(&captured[0]).unwrap();
+        let item = &redacted["items"][0];
+
+        // numeric ids stay numbers
+        assert!(item["timelineid"].is_u64() && item["timelineid"] != 42);
+        assert!(
+            item["vlastnik"] != "Teacher12"
+                && item["vlastnik"].as_str().unwrap().starts_with("Teacher")
+        );
+        assert_eq!(item["vlastnik_meno"], "REDACTED");
+        assert_eq!(item["name"], "Mathematics");
+
+        let data: serde_json::Value = serde_json::from_str(item["data"].as_str().unwrap()).unwrap();
+        assert!(data["studentid"].is_u64() && data["studentid"] != 1234);
+        assert_eq!(data["ucitelMeno"], "REDACTED");
+        assert_eq!(data["student_name"], "REDACTED");
+    }
+
+    #[test]
+    fn dbi_test() {
+        let mut test = TestEdupage::new("dbi");
+        test.login();
+
+        let edupage = &test.edupage;
 
         let homework = edupage
             .filter_timeline_by_item_type(crate::types::timeline::TimelineItemType::Homework);
@@ -103,35 +556,714 @@ mod tests {
 
         let classrooms = edupage.get_classrooms();
         assert_matches!(classrooms, Ok(_));
+
+        test.finish();
+    }
+
+    #[test]
+    fn classes_test() {
+        let mut edupage = replay_edupage("dbi");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        assert_matches!(edupage.get_classes(), Ok(ref classes) if classes.len() == 1);
+        assert_matches!(edupage.get_class_students(1), Ok(ref students) if students.len() == 2);
+        assert_matches!(edupage.get_class_teachers(1), Ok(ref teachers) if teachers.len() == 1);
+        assert_matches!(edupage.get_class_classroom(1), Ok(Some(ref classroom)) if classroom.id == Some(1));
+        assert_matches!(edupage.get_class_teachers(2), Ok(ref teachers) if teachers.is_empty());
+    }
+
+    #[test]
+    fn parents_test() {
+        let mut edupage = replay_edupage("dbi");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        assert_matches!(edupage.get_parents(), Ok(ref parents) if parents.len() == 1);
+        assert_matches!(
+            edupage.get_student_parents(1234),
+            Ok(ref parents) if parents.len() == 1 && parents[0].id == Some(5678)
+        );
+        assert_matches!(edupage.get_student_parents(1235), Ok(ref parents) if parents.is_empty());
+        assert_matches!(edupage.get_student_parents(1), Ok(ref parents) if parents.is_empty());
+        assert_matches!(
+            edupage.get_parent_children(5678),
+            Ok(ref children) if children.len() == 1 && children[0].id == Some(1234)
+        );
+    }
+
+    #[test]
+    fn dbi_index_test() {
+        let mut edupage = replay_edupage("dbi");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        let dbi = edupage.dbi().unwrap();
+        assert_matches!(dbi.teacher(2), Some(teacher) if teacher.short == "JO");
+        assert_matches!(dbi.teacher(3), None);
+        assert_eq!(dbi.class_students(1).count(), 2);
+
+        // the vectors are public, so the index can get out of date
+        let students = &mut edupage.data.as_mut().unwrap().dbi.students;
+        students.swap(0, 1);
+        students[0].class_id = Some(2);
+        let dbi = edupage.dbi().unwrap();
+        assert_matches!(dbi.student(1234), Some(student) if student.class_id == Some(1));
+        assert_eq!(dbi.class_students(1).count(), 1);
+        assert_eq!(dbi.class_students(2).count(), 1);
+        assert_matches!(
+            dbi.parent_children(5678).collect::<Vec<_>>()[..],
+            [student] if student.id == Some(1234)
+        );
     }
 
     #[test]
     fn timetable_test() {
-        dotenv::dotenv().ok();
+        let mut test = TestEdupage::new("timetable");
+        test.login();
+
+        // the recorded data only has timetables for a few days, so use one of them instead of today
+        let date = test
+            .edupage
+            .user_data()
+            .unwrap()
+            .dp
+            .dates
+            .keys()
+            .min()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .unwrap();
+
+        let result = test.edupage.get_timetable(date);
+        assert_matches!(result, Ok(_));
+
+        test.finish();
+    }
+
+    #[test]
+    fn substitution_test() {
+        let mut test = TestEdupage::new("substitution");
+        test.login();
+
+        let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let result = test.edupage.get_substitution_html(&date, &test.subdomain);
+        assert_matches!(result, Ok(_));
+
+        test.finish();
+    }
+
+    #[test]
+    fn error_test() {
+        use std::error::Error;
+
+        let error = EdupageError::http_status(503);
+        assert_eq!(error.code(), "http_error");
+        assert_eq!(error.status(), Some(503));
+        assert!(error.is_retryable());
+        assert!(!error.is_auth());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "http_error",
+                "message": "http error: Edupage responded with status 503",
+                "status": 503,
+            })
+        );
+
+        let json = serde_json::to_string(&error).unwrap();
+        let restored: EdupageError = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.code(), "http_error");
+        assert_eq!(restored.status(), Some(503));
+        assert_eq!(restored.to_string(), error.to_string());
+
+        let error = EdupageError::parse(serde_json::from_str::<u8>("x").unwrap_err());
+        assert!(error.source().is_some());
+        assert!(!error.is_retryable());
 
-        use crate::edupage::Edupage;
+        assert!(EdupageError::SessionExpired.is_auth());
+        assert_eq!(
+            EdupageError::SessionExpired.to_string(),
+            "the session expired"
+        );
+    }
+
+    /// Answers every request with `503` and counts the requests.
+    #[derive(Clone, Default)]
+    struct UnavailableTransport {
+        requests: Arc<Mutex<usize>>,
+    }
 
-        let subdomain = get_env_var("SUBDOMAIN");
-        let username = get_env_var("USERNAME");
-        let password = get_env_var("PASSWORD");
+    impl Transport for UnavailableTransport {
+        fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+            *self.requests.lock().unwrap() += 1;
 
-        if [&subdomain, &username, &password].contains(&&None) {
-            debug_assert_ne!(subdomain, None);
-            debug_assert_ne!(username, None);
-            debug_assert_ne!(password, None);
+            Ok(HttpResponse::new(request.url, 503))
         }
+    }
+
+    #[test]
+    fn retry_test() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1))
+            .jitter(false);
+
+        let backoffs: Vec<u128> = (0..6)
+            .map(|attempt| policy.backoff(attempt, None).as_millis())
+            .collect();
+        assert_eq!(backoffs, [100, 200, 400, 800, 1000, 1000]);
+
+        let url = reqwest::Url::parse("https://demo.edupage.org/").unwrap();
+        let mut response = HttpResponse::new(url, 429);
+        response
+            .headers
+            .push(("Retry-After".to_string(), "5".to_string()));
+        assert_eq!(policy.backoff(0, Some(&response)), Duration::from_secs(1));
+
+        // only GET requests are retried by default
+        let requests = |policy: RetryPolicy, request_type: RequestType| {
+            let transport = UnavailableTransport::default();
+            let edupage = Edupage::builder()
+                .transport(transport.clone())
+                .retry_policy(policy.initial_backoff(Duration::ZERO))
+                .build()
+                .unwrap();
+
+            let response = edupage.request(
+                "https://demo.edupage.org/".to_string(),
+                request_type,
+                None,
+                None,
+            );
+            assert_matches!(response, Ok(ref response) if response.status == 503);
+
+            *transport.requests.lock().unwrap()
+        };
+        assert_eq!(
+            requests(RetryPolicy::new().max_retries(2), RequestType::GET),
+            3
+        );
+        assert_eq!(
+            requests(RetryPolicy::new().max_retries(2), RequestType::POST),
+            1
+        );
+        assert_eq!(
+            requests(
+                RetryPolicy::new().max_retries(2).retry_post(true),
+                RequestType::POST
+            ),
+            3
+        );
+        assert_eq!(requests(RetryPolicy::none(), RequestType::GET), 1);
+
+        assert!(!EdupageError::HTTPError("Too many redirects!".into()).is_retryable());
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(EdupageError::http(refused).is_retryable());
+    }
+
+    #[test]
+    fn rate_limit_test() {
+        let rate_limiter = RateLimiter::new(10.0, 2);
+
+        // the bucket starts full
+        assert_eq!(rate_limiter.try_acquire(), None);
+        assert_eq!(rate_limiter.try_acquire(), None);
+
+        let wait = rate_limiter.try_acquire().unwrap();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(100));
+
+        // one token is refilled in 100 ms
+        std::thread::sleep(wait);
+        assert_eq!(rate_limiter.try_acquire(), None);
+        assert!(rate_limiter.try_acquire().is_some());
+    }
+
+    #[test]
+    fn offline_test() {
+        let mut edupage = Edupage::from_snapshot_file(fixture_path("userhome.html")).unwrap();
+        assert!(edupage.is_offline());
+
+        assert_matches!(edupage.get_teachers(), Ok(ref teachers) if teachers.len() == 2);
+        assert_matches!(
+            edupage.filter_timeline_by_item_type(crate::types::timeline::TimelineItemType::Homework),
+            Ok(ref items) if items.len() == 1
+        );
+
+        let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        assert_matches!(edupage.get_timetable(date), Ok(_));
+        assert_matches!(
+            edupage.get_substitution_html(&date, "demo"),
+            Err(EdupageError::Offline)
+        );
+        assert_matches!(
+            edupage.login("demo", "user", "password"),
+            Err(EdupageError::Offline)
+        );
+    }
+
+    #[test]
+    fn malformed_input_test() {
+        let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+
+        for entry in std::fs::read_dir(fixture_path("malformed")).unwrap() {
+            let path = entry.unwrap().path();
+            let snapshot = std::fs::read_to_string(&path).unwrap();
+
+            let result = Edupage::from_snapshot(&snapshot).and_then(|edupage| {
+                edupage.get_teachers()?;
+                edupage.get_students()?;
+                edupage.get_timetable(date)?;
+                Ok(())
+            });
+            assert!(result.is_err(), "{} was parsed", path.display());
+        }
+    }
+
+    #[test]
+    fn lenient_parsing_test() {
+        let lenient = |name: &str| {
+            let snapshot = std::fs::read_to_string(fixture_path("malformed").join(name)).unwrap();
+            Edupage::builder()
+                .parse_mode(ParseMode::Lenient)
+                .build_from_snapshot(&snapshot)
+        };
+
+        let edupage = lenient("teacher-invalid-gender.json").unwrap();
+        assert_eq!(
+            edupage.parse_warnings(),
+            [ParseWarning {
+                path: "dbi.teachers.1.gender".to_string(),
+                action: ParseAction::ReplacedWithDefault,
+                message: "Failed to deserialize gender: x".to_string(),
+            }]
+        );
+        assert_matches!(edupage.get_teacher_by_id(1), Ok(Some(ref t)) if t.gender.is_none());
+
+        let edupage = lenient("student-invalid-class.json").unwrap();
+        assert_matches!(edupage.parse_warnings(), [warning] if warning.path == "dbi.students.1234"
+            && warning.action == ParseAction::Skipped);
+        assert_matches!(edupage.get_student_by_id(1234), Ok(None));
+
+        let edupage = lenient("item-user-not-a-number.json").unwrap();
+        assert_eq!(
+            edupage.parse_warnings(),
+            [ParseWarning {
+                path: "items[0]".to_string(),
+                action: ParseAction::Skipped,
+                message: "Unexpected user id Ucitel-abc at items[0].user".to_string(),
+            }]
+        );
+
+        // the paths are the ones in edupage's json, even after the first item was left out
+        let edupage = lenient("items-two-unparseable.json").unwrap();
+        let paths: Vec<&str> = edupage
+            .parse_warnings()
+            .iter()
+            .map(|w| w.path.as_str())
+            .collect();
+        assert_eq!(paths, ["items[0]", "items[3]"]);
+        assert_eq!(edupage.user_data().unwrap().items.len(), 2);
+
+        assert_matches!(
+            lenient("not-json.json").map(|_| ()),
+            Err(EdupageError::ParseError(_))
+        );
+    }
+
+    #[test]
+    fn refresh_test() {
+        let mut test = TestEdupage::new("refresh");
+        test.login();
+
+        let edupage = &mut test.edupage;
+        let homework = |edupage: &Edupage| {
+            edupage.filter_timeline_by_item_type(crate::types::timeline::TimelineItemType::Homework)
+        };
+        assert_matches!(homework(edupage), Ok(ref items) if items.len() == 1);
+
+        // there is new homework in the timeline
+        assert_matches!(edupage.refresh(), Ok(ref changed) if changed == &[UserDataSection::Timeline]);
+        assert_matches!(homework(edupage), Ok(ref items) if items.len() == 2);
+
+        // there is a new teacher, but only the timeline is refreshed
+        assert_matches!(edupage.refresh_sections(&[UserDataSection::Timeline]), Ok(ref changed) if changed.is_empty());
+        assert_matches!(edupage.get_teachers(), Ok(ref teachers) if teachers.len() == 2);
 
-        let mut edupage = Edupage::new();
+        test.finish();
+    }
+
+    #[test]
+    fn search_test() {
+        let edupage = Edupage::from_snapshot_file(fixture_path("userhome.html")).unwrap();
 
-        let subdomain = subdomain.unwrap();
-        let username = username.unwrap();
-        let password = password.unwrap();
+        let names = |query: &str| {
+            edupage
+                .search(query)
+                .unwrap()
+                .iter()
+                .map(|hit| (hit.kind(), hit.entity.name(), hit.match_kind))
+                .collect::<Vec<_>>()
+        };
 
-        let login_result = edupage.login(&subdomain, &username, &password);
-        assert_matches!(login_result, Ok(_));
+        assert_eq!(
+            names("ÁLICE"),
+            [(
+                DBIEntityKind::Student,
+                "Alice Student".to_string(),
+                MatchKind::Exact
+            )]
+        );
+        assert_eq!(
+            names("jhon teach"),
+            [(
+                DBIEntityKind::Teacher,
+                "John Teacher".to_string(),
+                MatchKind::Fuzzy
+            )]
+        );
+        assert_eq!(
+            names("mat"),
+            [(
+                DBIEntityKind::Subject,
+                "Mathematics".to_string(),
+                MatchKind::Exact
+            )]
+        );
+        assert_eq!(
+            names("stu")
+                .iter()
+                .map(|(kind, _, _)| *kind)
+                .collect::<Vec<_>>(),
+            [
+                DBIEntityKind::Student,
+                DBIEntityKind::Student,
+                DBIEntityKind::Parent
+            ]
+        );
+        assert!(names("").is_empty());
+        assert!(names("xyz").is_empty());
+    }
 
-        let today = Utc::now().naive_local();
-        let result = edupage.get_timetable(today.date());
+    #[test]
+    fn active_dbi_test() {
+        let edupage = Edupage::from_snapshot_file(fixture_path("userhome.html")).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_matches!(edupage.get_active_teachers(date(2019, 9, 2)), Ok(ref teachers) if teachers.len() == 1);
+        assert_matches!(edupage.get_active_teachers(date(2024, 9, 2)), Ok(ref teachers) if teachers.len() == 2);
+        assert_matches!(edupage.get_active_students(date(2024, 8, 31)), Ok(ref students) if students.is_empty());
+        assert_matches!(edupage.get_class_students_on(1, date(2024, 9, 2)), Ok(ref students) if students.len() == 2);
+        assert_matches!(edupage.get_teachers_out(), Ok(ref teachers) if teachers.is_empty());
+    }
+
+    #[test]
+    fn profile_test() {
+        let edupage = Edupage::from_snapshot_file(fixture_path("userhome.html")).unwrap();
+
+        let me = edupage.me().unwrap();
+        assert_matches!(
+            me,
+            UserProfile::Student { id: 1234, student: Some(ref student), class: Some(ref class) }
+                if student.first_name == "Alice" && class.name == "1.A"
+        );
+        assert_eq!(
+            me.capabilities(),
+            Capabilities {
+                switch_children: false,
+                sign_into_lessons: true,
+                class_teacher: false,
+            }
+        );
+
+        let json = serde_json::to_string(&me).unwrap();
+        let restored: UserProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.id(), 1234);
+        assert_eq!(restored.capabilities(), me.capabilities());
+    }
+
+    #[test]
+    fn rpc_test() {
+        let mut test = TestEdupage::new("substitution");
+        test.login();
+
+        let result: Result<String, _> = test.edupage.rpc(
+            "/substitution/server/viewer.js",
+            "getSubstViewerDayDataHtml",
+            (
+                serde_json::Value::Null,
+                serde_json::json!({ "date": "2024-09-02", "mode": "classes" }),
+            ),
+        );
+        assert_matches!(result, Ok(ref html) if html.contains("Cancelled"));
+
+        test.finish();
+    }
+
+    #[test]
+    fn online_lesson_test() {
+        let (mut edupage, recorder) = recorded_replay_edupage("online_lesson");
+        edupage
+            .login(
+                TestEdupage::SUBDOMAIN,
+                TestEdupage::USERNAME,
+                TestEdupage::PASSWORD,
+            )
+            .unwrap();
+        edupage.enable_reauthentication(CredentialSource::Stored(Credentials::new(
+            TestEdupage::SUBDOMAIN.to_string(),
+            TestEdupage::USERNAME.to_string(),
+            TestEdupage::PASSWORD.to_string(),
+        )));
+
+        let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let lesson = edupage
+            .get_timetable(date)
+            .unwrap()
+            .into_iter()
+            .find(|lesson| lesson.is_online_lesson())
+            .unwrap();
+
+        // the first reply asks to reload, the second one has no `r`, which still means success
+        assert_matches!(lesson.sign_into_lesson(&edupage), Ok(()));
+
+        // the session was renewed once and the call was sent again with the new gsec hash
+        let interactions = recorder.cassette().interactions;
+        let logins = interactions
+            .iter()
+            .filter(|i| i.request.url.ends_with("/login/edubarLogin.php"))
+            .count();
+        assert_eq!(logins, 2);
+
+        let calls: Vec<_> = interactions
+            .iter()
+            .filter(|i| i.request.url.contains("onlinelesson.js"))
+            .collect();
+        assert_eq!(calls.len(), 2);
+        assert_matches!(calls[1].request.body, Some(ref body) if body.contains("5e6f7a8b"));
+    }
+
+    #[test]
+    fn cloud_upload_test() {
+        let mut test = TestEdupage::new("cloud_upload");
+        test.login();
+
+        let result = test.edupage.upload(fixture_path("upload.txt"));
         assert_matches!(result, Ok(_));
+
+        test.finish();
+    }
+}
+
+#[cfg(all(feature = "async", not(feature = "node-types")))]
+#[cfg(test)]
+mod async_tests {
+    use std::future::Future;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use chrono::NaiveDate;
+
+    use crate::edupage::{AsyncEdupage, EdupageError, RequestType, UserDataSource};
+    use crate::traits::{
+        AsyncCloud, AsyncLogin, AsyncReauthentication, AsyncSubstitution, AsyncTimetable,
+        CredentialSource, Credentials, Timetable, DBI,
+    };
+    use crate::transport::{
+        AsyncTransport, HttpRequest, HttpResponse, RecordingTransport, ReplayTransport,
+        RetryPolicy, TransportFuture,
+    };
+
+    const SUBDOMAIN: &str = "demo";
+    const USERNAME: &str = "user";
+    const PASSWORD: &str = "password";
+
+    fn fixture_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    /// Runs `future` on a new single threaded runtime.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// An `AsyncEdupage` answering from the cassette, the requests are recorded too, so the
+    /// tests can check what was sent.
+    fn replay_edupage(cassette: &str) -> (AsyncEdupage, RecordingTransport<ReplayTransport>) {
+        let cassette = fixture_path("cassettes").join(format!("{}.json", cassette));
+        let recorder = RecordingTransport::new(ReplayTransport::load(cassette).unwrap());
+
+        let edupage = AsyncEdupage::builder()
+            .async_transport(recorder.clone())
+            .build_async()
+            .unwrap();
+
+        (edupage, recorder)
+    }
+
+    #[test]
+    fn login_test() {
+        let (mut edupage, _) = replay_edupage("timetable");
+
+        assert_matches!(
+            block_on(edupage.login(SUBDOMAIN, USERNAME, PASSWORD)),
+            Ok(_)
+        );
+        assert!(edupage.logged_in());
+        assert_matches!(edupage.get_teachers(), Ok(ref teachers) if !teachers.is_empty());
+
+        // the recorded data only has timetables for a few days, so use one of them instead of today
+        let date = edupage
+            .user_data()
+            .unwrap()
+            .dp
+            .dates
+            .keys()
+            .min()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .unwrap();
+        assert_matches!(edupage.get_timetable(date), Ok(_));
+    }
+
+    #[test]
+    fn reauthentication_test() {
+        let (mut edupage, recorder) = replay_edupage("online_lesson");
+
+        block_on(async {
+            edupage.login(SUBDOMAIN, USERNAME, PASSWORD).await.unwrap();
+            edupage.enable_reauthentication(CredentialSource::Stored(Credentials::new(
+                SUBDOMAIN.to_string(),
+                USERNAME.to_string(),
+                PASSWORD.to_string(),
+            )));
+
+            let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+            let lesson = edupage
+                .get_timetable(date)
+                .unwrap()
+                .into_iter()
+                .find(|lesson| lesson.is_online_lesson())
+                .unwrap();
+
+            assert_matches!(edupage.sign_into_lesson(&lesson).await, Ok(()));
+        });
+
+        // the session was renewed once and the call was sent again with the new gsec hash
+        let interactions = recorder.cassette().interactions;
+        let logins = interactions
+            .iter()
+            .filter(|i| i.request.url.ends_with("/login/edubarLogin.php"))
+            .count();
+        assert_eq!(logins, 2);
+
+        let calls: Vec<_> = interactions
+            .iter()
+            .filter(|i| i.request.url.contains("onlinelesson.js"))
+            .collect();
+        assert_eq!(calls.len(), 2);
+        assert_matches!(calls[1].request.body, Some(ref body) if body.contains("5e6f7a8b"));
+    }
+
+    #[test]
+    fn offline_test() {
+        let mut edupage = AsyncEdupage::from_snapshot_file(fixture_path("userhome.html")).unwrap();
+        assert!(edupage.is_offline());
+
+        assert_matches!(edupage.get_teachers(), Ok(ref teachers) if teachers.len() == 2);
+
+        let date = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        assert_matches!(edupage.get_timetable(date), Ok(_));
+        assert_matches!(
+            block_on(edupage.get_substitution_html(&date, SUBDOMAIN)),
+            Err(EdupageError::Offline)
+        );
+        assert_matches!(
+            block_on(edupage.login(SUBDOMAIN, USERNAME, PASSWORD)),
+            Err(EdupageError::Offline)
+        );
+    }
+
+    #[test]
+    fn cloud_upload_test() {
+        let (mut edupage, recorder) = replay_edupage("cloud_upload");
+
+        // a file name that would break the multipart body if it was not escaped
+        let directory = std::env::temp_dir().join(format!("cdupage-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("a\"b\r\n.txt");
+        std::fs::copy(fixture_path("upload.txt"), &path).unwrap();
+
+        let result = block_on(async {
+            edupage.login(SUBDOMAIN, USERNAME, PASSWORD).await.unwrap();
+
+            edupage.upload(path).await
+        });
+        std::fs::remove_dir_all(directory).unwrap();
+        assert_matches!(result, Ok(_));
+
+        let interactions = recorder.cassette().interactions;
+        let upload = interactions
+            .iter()
+            .find(|i| i.request.url.contains("uploadAtt"))
+            .unwrap();
+        assert_matches!(
+            upload.request.body,
+            Some(ref body) if body.contains("filename=\"a%22b%0D%0A.txt\"\r\n")
+        );
+    }
+
+    /// Answers every request with `503 Service Unavailable` and counts them.
+    #[derive(Clone, Default)]
+    struct UnavailableTransport {
+        requests: Arc<Mutex<u32>>,
+    }
+
+    impl AsyncTransport for UnavailableTransport {
+        fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+            *self.requests.lock().unwrap() += 1;
+
+            Box::pin(async move { Ok(HttpResponse::new(request.url, 503)) })
+        }
+    }
+
+    #[test]
+    fn retry_test() {
+        let transport = UnavailableTransport::default();
+        let edupage = AsyncEdupage::builder()
+            .async_transport(transport.clone())
+            .retry_policy(
+                RetryPolicy::new()
+                    .max_retries(2)
+                    .initial_backoff(Duration::ZERO),
+            )
+            .build_async()
+            .unwrap();
+
+        let response = block_on(edupage.request(
+            "https://demo.edupage.org/".to_string(),
+            RequestType::GET,
+            None,
+            None,
+        ));
+        assert_matches!(response, Ok(ref response) if response.status == 503);
+        assert_eq!(*transport.requests.lock().unwrap(), 3);
     }
 }
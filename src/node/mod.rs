@@ -1,4 +1,4 @@
-use chrono::{TimeZone, Utc};
+use chrono::{NaiveDate, TimeZone, Utc};
 use node_bindgen::{
     core::{val::JsObject, NjError, TryIntoJs},
     derive::node_bindgen,
@@ -9,10 +9,10 @@ use serde_json::Value;
 // use tslink::tslink;
 use crate::{
     edupage::{Edupage as IEdupage, EdupageError},
-    traits::{Login, Ringing, Timeline, Timetable, DBI},
+    traits::{Login, Profile, Ringing, Timeline, Timetable, DBI},
     types::{
-        DBIBase, RingingTime, Student, Teacher, TimelineItem, TimelineItemType,
-        Timetable as EduTimetable,
+        Class, DBIBase, Parent, RingingTime, Student, Teacher, TimelineItem, TimelineItemType,
+        Timetable as EduTimetable, UserProfile,
     },
 };
 
@@ -79,6 +79,7 @@ impl TryIntoJs for IEdupage {
         js_env: &node_bindgen::core::val::JsEnv,
     ) -> Result<node_bindgen::sys::napi_value, node_bindgen::core::NjError> {
         let mut instance = JsObject::new(*js_env, js_env.create_object()?);
+        let gsec_hash = self.gsec_hash();
 
         instance.set_property("isLoggedIn", js_env.create_boolean(self.is_logged_in)?)?;
 
@@ -88,7 +89,7 @@ impl TryIntoJs for IEdupage {
             instance.set_property("data", js_env.get_null()?)?;
         }
 
-        if let Some(gsec_hash) = self.gsec_hash {
+        if let Some(gsec_hash) = gsec_hash {
             instance.set_property("gsecHash", js_env.create_string_utf8(&gsec_hash)?)?;
         } else {
             instance.set_property("gsecHash", js_env.get_null()?)?;
@@ -104,6 +105,14 @@ impl TryIntoJs for IEdupage {
     }
 }
 
+/// The date of a unix timestamp (in seconds) passed from javascript.
+fn timestamp_date(timestamp: i64) -> Result<NaiveDate, SerdeIntoJs<EdupageError>> {
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(datetime) => Ok(datetime.naive_local().date()),
+        None => Err(SerdeIntoJs(EdupageError::Other("Invalid date!".into()))),
+    }
+}
+
 #[node_bindgen]
 struct Edupage(IEdupage);
 
@@ -126,6 +135,16 @@ impl Edupage {
             .map_err(|e| SerdeIntoJs(e))
     }
 
+    #[node_bindgen]
+    pub fn logout(&mut self) -> Result<(), SerdeIntoJs<EdupageError>> {
+        self.0.logout().map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn me(&self) -> Result<SerdeIntoJs<UserProfile>, SerdeIntoJs<EdupageError>> {
+        self.0.me().map(SerdeIntoJs).map_err(SerdeIntoJs)
+    }
+
     #[node_bindgen]
     pub fn get_teachers(&self) -> Result<Vec<SerdeIntoJs<Teacher>>, SerdeIntoJs<EdupageError>> {
         self.0
@@ -214,6 +233,141 @@ impl Edupage {
             .map_err(|e| SerdeIntoJs(e))
     }
 
+    #[node_bindgen]
+    pub fn get_classes(&self) -> Result<Vec<SerdeIntoJs<Class>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_classes()
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_class_by_id(
+        &self,
+        id: i64,
+    ) -> Result<Option<SerdeIntoJs<Class>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_class_by_id(id)
+            .map(|r| r.map(SerdeIntoJs))
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_class_students(
+        &self,
+        class_id: i64,
+    ) -> Result<Vec<SerdeIntoJs<Student>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_class_students(class_id)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_class_teachers(
+        &self,
+        class_id: i64,
+    ) -> Result<Vec<SerdeIntoJs<Teacher>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_class_teachers(class_id)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_class_classroom(
+        &self,
+        class_id: i64,
+    ) -> Result<Option<SerdeIntoJs<DBIBase>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_class_classroom(class_id)
+            .map(|r| r.map(SerdeIntoJs))
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_parents(&self) -> Result<Vec<SerdeIntoJs<Parent>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_parents()
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_parent_by_id(
+        &self,
+        id: i64,
+    ) -> Result<Option<SerdeIntoJs<Parent>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_parent_by_id(id)
+            .map(|r| r.map(SerdeIntoJs))
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_student_parents(
+        &self,
+        student_id: i64,
+    ) -> Result<Vec<SerdeIntoJs<Parent>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_student_parents(student_id)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_parent_children(
+        &self,
+        parent_id: i64,
+    ) -> Result<Vec<SerdeIntoJs<Student>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_parent_children(parent_id)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_active_teachers(
+        &self,
+        date_timestamp: i64,
+    ) -> Result<Vec<SerdeIntoJs<Teacher>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_active_teachers(timestamp_date(date_timestamp)?)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_active_students(
+        &self,
+        date_timestamp: i64,
+    ) -> Result<Vec<SerdeIntoJs<Student>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_active_students(timestamp_date(date_timestamp)?)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_class_students_on(
+        &self,
+        class_id: i64,
+        date_timestamp: i64,
+    ) -> Result<Vec<SerdeIntoJs<Student>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_class_students_on(class_id, timestamp_date(date_timestamp)?)
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
+    #[node_bindgen]
+    pub fn get_teachers_out(&self) -> Result<Vec<SerdeIntoJs<Teacher>>, SerdeIntoJs<EdupageError>> {
+        self.0
+            .get_teachers_out()
+            .map(|r| r.into_iter().map(SerdeIntoJs).collect())
+            .map_err(SerdeIntoJs)
+    }
+
     #[node_bindgen]
     pub fn filter_timeline_by_item_type(
         &self,
@@ -223,7 +377,7 @@ impl Edupage {
             Ok(v) => v,
             Err(_) => {
                 return Err(SerdeIntoJs(EdupageError::ParseError(
-                    "Invalid item type!".to_string(),
+                    "Invalid item type!".into(),
                 )))
             }
         };
@@ -247,7 +401,7 @@ impl Edupage {
                     Ok(v) => v,
                     Err(_) => {
                         return Err(SerdeIntoJs(EdupageError::ParseError(
-                            "Invalid item type!".to_string(),
+                            "Invalid item type!".into(),
                         )))
                     }
                 },
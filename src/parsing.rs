@@ -0,0 +1,420 @@
+//! How strictly the user's data from edupage is parsed.
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde_json::Value;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde_path_to_error::Segment;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::{
+    edupage::EdupageError,
+    types::{
+        dbi::DBIBase,
+        person::{Class, Parent, Student, Teacher},
+        timeline::TimelineItem,
+        timetable::PlanItem,
+        RingingTime, UserData,
+    },
+};
+
+/// How to handle values in the user's data that do not match what the crate expects.
+///
+/// Schools can customise edupage a lot, so the data can contain values the crate does not know
+/// about (an unknown gender letter, a new type of lesson, ...).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Any unexpected value makes the whole parsing (and so the login) fail.
+    #[default]
+    Strict,
+    /// Unexpected values are replaced with defaults (`None`) where possible, otherwise the entry
+    /// containing them (a teacher, a timeline item, a lesson, ...) is skipped. Every change is
+    /// reported as a [`ParseWarning`].
+    ///
+    /// Parsing still fails when the data is not json or something required (like the user's
+    /// id) is unusable.
+    Lenient,
+}
+
+/// What was done with a value that could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseAction {
+    /// The value was replaced with its default.
+    ReplacedWithDefault,
+    /// The value was left out, e.g. the whole teacher was skipped.
+    Skipped,
+}
+
+/// A value in the user's data that could not be parsed in [`ParseMode::Lenient`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseWarning {
+    /// The path of the value in edupage's json, e.g. `dbi.teachers.12.gender` or `items[3]`.
+    pub path: String,
+    pub action: ParseAction,
+    /// Why the value could not be parsed.
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            ParseAction::ReplacedWithDefault => "replaced with default",
+            ParseAction::Skipped => "skipped",
+        };
+
+        write!(f, "{} {}: {}", self.path, action, self.message)
+    }
+}
+
+/// How many values can be changed before lenient parsing gives up.
+///
+/// Every change parses the entry it is in again (or the whole data, for values outside of the
+/// entries), so this also limits how long parsing can take.
+#[cfg(any(feature = "blocking", feature = "async"))]
+const MAX_REPAIRS: usize = 1_000;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum PathSegment {
+    Index(usize),
+    Key(String),
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn path_to_string(path: &[PathSegment]) -> String {
+    let mut result = String::new();
+
+    for segment in path {
+        match segment {
+            PathSegment::Index(index) => result.push_str(&format!("[{}]", index)),
+            PathSegment::Key(key) => {
+                if !result.is_empty() {
+                    result.push('.');
+                }
+                result.push_str(key);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn key_path(keys: &[&str]) -> Vec<PathSegment> {
+    keys.iter()
+        .map(|key| PathSegment::Key(key.to_string()))
+        .collect()
+}
+
+/// The path of the error, up to the first segment that does not point into the json
+/// (like an enum variant).
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn error_path(path: &serde_path_to_error::Path) -> Vec<PathSegment> {
+    path.iter()
+        .map_while(|segment| match segment {
+            Segment::Seq { index } => Some(PathSegment::Index(*index)),
+            Segment::Map { key } => Some(PathSegment::Key(key.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn value_at<'a>(value: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        PathSegment::Index(index) => value.get_mut(*index),
+        PathSegment::Key(key) => value.get_mut(key.as_str()),
+    })
+}
+
+/// Removes the value at `path` from its parent object or array.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn remove_value(value: &mut Value, path: &[PathSegment]) -> bool {
+    let Some((last, parent_path)) = path.split_last() else {
+        return false;
+    };
+
+    match (value_at(value, parent_path), last) {
+        (Some(Value::Array(items)), PathSegment::Index(index)) if *index < items.len() => {
+            items.remove(*index);
+            true
+        }
+        (Some(Value::Object(map)), PathSegment::Key(key)) => map.remove(key).is_some(),
+        _ => false,
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn is_under(path: &str, parent: &str) -> bool {
+    path.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+/// How the entries are stored in a collection of the user's data.
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Clone, Copy)]
+enum Collection {
+    Array,
+    /// An object with the ids as the keys, like in the dbi.
+    Map,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+enum Repaired<T> {
+    Parsed(T),
+    /// The value itself can not be parsed, so it has to be left out.
+    Unusable(serde_json::Error),
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Default)]
+struct Repairer {
+    warnings: Vec<ParseWarning>,
+    repairs: usize,
+    /// The original indices of the entries removed from the arrays, by the original path of
+    /// the array. Used to report the paths as they are in edupage's json.
+    removed: HashMap<Vec<PathSegment>, Vec<usize>>,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl Repairer {
+    fn count_repair(&mut self) -> Result<(), EdupageError> {
+        self.repairs += 1;
+
+        if self.repairs > MAX_REPAIRS {
+            return Err(EdupageError::ParseError(
+                "Too many values could not be parsed".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The path before any entries were removed from the arrays.
+    fn original_path(&self, path: &[PathSegment]) -> Vec<PathSegment> {
+        let mut original: Vec<PathSegment> = Vec::with_capacity(path.len());
+
+        for segment in path {
+            let segment = match (segment, self.removed.get(&original)) {
+                (PathSegment::Index(index), Some(removed)) => {
+                    // the removed indices are sorted, so every one before shifts the index by one
+                    let mut index = *index;
+                    for removed_index in removed {
+                        if *removed_index <= index {
+                            index += 1;
+                        }
+                    }
+                    PathSegment::Index(index)
+                }
+                (segment, _) => segment.clone(),
+            };
+
+            original.push(segment);
+        }
+
+        original
+    }
+
+    /// Removes the value at `path` (relative to `value`, which is at `base`).
+    fn remove(&mut self, value: &mut Value, base: &[PathSegment], path: &[PathSegment]) -> bool {
+        let original = self.original_path(&[base, path].concat());
+
+        if !remove_value(value, path) {
+            return false;
+        }
+
+        if let Some((PathSegment::Index(index), parent)) = original.split_last() {
+            let removed = self.removed.entry(parent.to_vec()).or_default();
+            let position = removed.partition_point(|r| r < index);
+            removed.insert(position, *index);
+        }
+
+        true
+    }
+
+    /// The warnings of the values inside `path` are replaced by the one for `path`, but their
+    /// message is kept as it tells what was wrong in the first place.
+    fn take_inner_message(&mut self, path: &str, message: String) -> String {
+        match self.warnings.iter().position(|w| is_under(&w.path, path)) {
+            Some(position) => {
+                let inner = &self.warnings[position];
+                let message = format!("{} at {}", inner.message, inner.path);
+                self.warnings.retain(|w| !is_under(&w.path, path));
+                message
+            }
+            None => message,
+        }
+    }
+
+    /// Changes `value` (which is at `base` in edupage's json) until it can be parsed as `T`.
+    fn repair<T: DeserializeOwned>(
+        &mut self,
+        value: &mut Value,
+        base: &[PathSegment],
+    ) -> Result<Repaired<T>, EdupageError> {
+        loop {
+            let error = match serde_path_to_error::deserialize::<_, T>(&*value) {
+                Ok(parsed) => return Ok(Repaired::Parsed(parsed)),
+                Err(e) => e,
+            };
+
+            let mut path = error_path(error.path());
+            // the error can point to a field that is missing, so go up until there is something
+            // to change
+            while !path.is_empty() && value_at(value, &path).is_none() {
+                path.pop();
+            }
+
+            if path.is_empty() {
+                return Ok(Repaired::Unusable(error.into_inner()));
+            }
+
+            self.count_repair()?;
+
+            let path_string = path_to_string(&self.original_path(&[base, &path].concat()));
+            let message = self.take_inner_message(&path_string, error.inner().to_string());
+            let previous = self.warnings.iter().rposition(|w| w.path == path_string);
+
+            match (value_at(value, &path), previous) {
+                // replacing the value with null was not enough, so it is left out
+                (Some(target), Some(previous))
+                    if target.is_null()
+                        && self.warnings[previous].action == ParseAction::ReplacedWithDefault =>
+                {
+                    if !self.remove(value, base, &path) {
+                        return Ok(Repaired::Unusable(error.into_inner()));
+                    }
+                    self.warnings[previous].action = ParseAction::Skipped;
+                }
+                (Some(target), _) if !target.is_null() => {
+                    *target = Value::Null;
+                    self.warnings.push(ParseWarning {
+                        path: path_string,
+                        action: ParseAction::ReplacedWithDefault,
+                        message,
+                    });
+                }
+                _ => {
+                    if !self.remove(value, base, &path) {
+                        return Ok(Repaired::Unusable(error.into_inner()));
+                    }
+                    self.warnings.push(ParseWarning {
+                        path: path_string,
+                        action: ParseAction::Skipped,
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Repairs the entries of the collection at `keys` one by one, the entries that can not be
+    /// parsed are left out.
+    fn repair_collection<T: DeserializeOwned>(
+        &mut self,
+        value: &mut Value,
+        keys: &[&str],
+        collection: Collection,
+    ) -> Result<(), EdupageError> {
+        let path = key_path(keys);
+
+        let entries: Vec<PathSegment> = match (value_at(value, &path), collection) {
+            (Some(Value::Array(items)), Collection::Array) => {
+                (0..items.len()).map(PathSegment::Index).collect()
+            }
+            (Some(Value::Object(map)), Collection::Map) => {
+                map.keys().cloned().map(PathSegment::Key).collect()
+            }
+            // anything else is reported when the whole data is parsed
+            _ => return Ok(()),
+        };
+
+        let mut unusable = Vec::new();
+
+        for segment in entries {
+            let entry_path = [path.as_slice(), std::slice::from_ref(&segment)].concat();
+            let Some(entry) = value_at(value, &entry_path) else {
+                continue;
+            };
+
+            if let Repaired::Unusable(error) = self.repair::<T>(entry, &entry_path)? {
+                self.count_repair()?;
+
+                let path_string = path_to_string(&entry_path);
+                let message = self.take_inner_message(&path_string, error.to_string());
+                self.warnings.push(ParseWarning {
+                    path: path_string,
+                    action: ParseAction::Skipped,
+                    message,
+                });
+                unusable.push(entry_path);
+            }
+        }
+
+        // from the end, so the indices of the other unusable entries do not change
+        for entry_path in unusable.iter().rev() {
+            self.remove(value, &[], entry_path);
+        }
+
+        Ok(())
+    }
+
+    /// Repairs the entries of the big collections before the whole data is parsed, so fixing an
+    /// entry only parses that entry again and not all the data.
+    fn repair_entries(&mut self, value: &mut Value) -> Result<(), EdupageError> {
+        use Collection::{Array, Map};
+
+        self.repair_collection::<TimelineItem>(value, &["items"], Array)?;
+        self.repair_collection::<RingingTime>(value, &["zvonenia"], Array)?;
+        self.repair_collection::<Teacher>(value, &["dbi", "teachers"], Map)?;
+        self.repair_collection::<Class>(value, &["dbi", "classes"], Map)?;
+        self.repair_collection::<DBIBase>(value, &["dbi", "subjects"], Map)?;
+        self.repair_collection::<DBIBase>(value, &["dbi", "classrooms"], Map)?;
+        self.repair_collection::<Student>(value, &["dbi", "students"], Map)?;
+        self.repair_collection::<Parent>(value, &["dbi", "parents"], Map)?;
+
+        let dates: Vec<String> = match value.pointer("/dp/dates") {
+            Some(Value::Object(dates)) => dates.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        for date in dates {
+            self.repair_collection::<PlanItem>(value, &["dp", "dates", &date, "plan"], Array)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the user's data, replacing or removing the values that make it fail in
+/// [`ParseMode::Lenient`].
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn parse_user_data(
+    json: &str,
+    mode: ParseMode,
+) -> Result<(UserData, Vec<ParseWarning>), EdupageError> {
+    if mode == ParseMode::Strict {
+        let data: UserData = serde_json::from_str(json).map_err(EdupageError::parse)?;
+        data.dbi.build_index();
+
+        return Ok((data, Vec::new()));
+    }
+
+    let mut value: Value = serde_json::from_str(json).map_err(EdupageError::parse)?;
+    let mut repairer = Repairer::default();
+
+    repairer.repair_entries(&mut value)?;
+
+    match repairer.repair::<UserData>(&mut value, &[])? {
+        Repaired::Parsed(data) => {
+            data.dbi.build_index();
+            Ok((data, repairer.warnings))
+        }
+        Repaired::Unusable(error) => Err(EdupageError::parse(error)),
+    }
+}
@@ -1,10 +1,24 @@
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::path::Path;
 use std::path::PathBuf;
 
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::edupage::RequestType;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::transport::HttpRequest;
+#[cfg(any(feature = "blocking", feature = "async"))]
 use crate::{
-    edupage::{Edupage, EdupageError},
-    types::{CloudFile, EdupageCloudResponse},
+    capture::PayloadKind,
+    edupage::is_login_redirect,
+    types::{EdupageCloudResponse, EdupageCloudResponseStatus},
 };
-use reqwest::blocking::multipart::Form;
+use crate::{edupage::EdupageError, types::CloudFile};
+#[cfg(feature = "async")]
+use std::future::Future;
 
 pub trait Cloud {
     /// Upload file to EduPage cloud.
@@ -16,64 +30,160 @@ pub trait Cloud {
     /// You can find all supported file extensions on this
     /// [Edupage help site](https://help.edupage.org/?p=u1/u113/u132/u362/u467).
     ///
-    /// If you are willing to upload some files, you will probably have to increase the request timeout.
+    /// If you are willing to upload some files, you will probably have to increase the request timeout
+    /// with [`crate::edupage::EdupageBuilder::timeout`].
     fn upload(&self, file: PathBuf) -> Result<CloudFile, EdupageError>;
 }
 
+/// The async version of [`Cloud`].
+#[cfg(feature = "async")]
+pub trait AsyncCloud {
+    fn upload(&self, file: PathBuf)
+        -> impl Future<Output = Result<CloudFile, EdupageError>> + Send;
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_cloud_response(text: &str) -> Result<CloudFile, EdupageError> {
+    let cloud_response =
+        serde_json::from_str::<EdupageCloudResponse>(text).map_err(EdupageError::serialization)?;
+
+    match cloud_response.status {
+        EdupageCloudResponseStatus::Ok => {
+            cloud_response.response.ok_or(EdupageError::InvalidResponse)
+        }
+        EdupageCloudResponseStatus::Other(status_type) => Err(EdupageError::Other(
+            format!("Edupage returned a {status_type} status").into(),
+        )),
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn upload_file_name(path: &Path) -> Result<String, EdupageError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or(EdupageError::Other("Invalid path!".into()))
+}
+
+/// Encodes a single file as `multipart/form-data`.
+///
+/// Returns the content type (with the boundary) and the body.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn multipart_body(field: &str, file_name: &str, contents: &[u8]) -> (String, Vec<u8>) {
+    let boundary = format!(
+        "cdupage-{:x}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+
+    let mut body = format!(
+        "--{boundary}\r\n\
+        Content-Disposition: form-data; name=\"{field}\"; filename=\"{}\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n",
+        escape_file_name(file_name)
+    )
+    .into_bytes();
+    body.extend_from_slice(contents);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+/// Percent-encodes the characters that would end the quoted file name or the header line, the
+/// same way browsers do.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn escape_file_name(file_name: &str) -> String {
+    file_name
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Builds the request uploading the `contents` of the file called `file_name` to `url`.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn upload_request(
+    url: &str,
+    file_name: &str,
+    contents: &[u8],
+) -> Result<HttpRequest, EdupageError> {
+    let (content_type, body) = multipart_body("att", file_name, contents);
+
+    // we have to send a multipart file so we cannot use the built-in request method
+    Ok(HttpRequest {
+        method: RequestType::POST,
+        url: reqwest::Url::parse(url).map_err(EdupageError::parse)?,
+        headers: vec![("Content-Type".to_string(), content_type)],
+        body: Some(body),
+    })
+}
+
+#[cfg(feature = "blocking")]
 impl Cloud for Edupage {
     fn upload(&self, path: PathBuf) -> Result<CloudFile, EdupageError> {
+        self.ensure_online()?;
+
         if !self.is_logged_in {
             return Err(EdupageError::NotLoggedIn);
         }
 
-        // the user is logged in, so subdomain cannot be `None`
-        let url = format!(
-            "https://{}.edupage.org/timeline/?akcia=uploadAtt",
-            self.subdomain.clone().unwrap()
-        );
-
-        let path_str = path
-            .to_str()
-            .ok_or(EdupageError::Other("Invalid path!".to_string()))?;
-
-        let form = Form::new()
-            .file("att", path_str)
-            .map_err(|err| EdupageError::Other(err.to_string()))?;
-
-        // we have to send a multipart file so we cannot use the built-in request method
-        let response = self
-            .client
-            .post(url)
-            .multipart(form)
-            .send()
-            .map_err(|err| EdupageError::HTTPError(err.to_string()))?;
-
-        let cloud_response = response
-            .json::<EdupageCloudResponse>()
-            .map_err(|err| EdupageError::SerializationError(err.to_string()))?;
-
-        match cloud_response.status {
-            crate::types::EdupageCloudResponseStatus::Ok => {
-                // we can safely unwrap as the response is Ok
-                Ok(cloud_response.response.unwrap())
+        let file_name = upload_file_name(&path)?;
+        let contents = std::fs::read(&path).map_err(EdupageError::other)?;
+
+        self.with_reauthentication(|| {
+            let url = self.url("/timeline/?akcia=uploadAtt")?;
+
+            let response = self.send(upload_request(&url, &file_name, &contents)?)?;
+
+            if is_login_redirect(response.url()) {
+                return Err(EdupageError::SessionExpired);
             }
-            crate::types::EdupageCloudResponseStatus::Other(status_type) => Err(
-                EdupageError::Other(format!("Edupage returned a {status_type} status")),
-            ),
+
+            let text = response.text().map_err(EdupageError::parse)?;
+            self.capture(PayloadKind::CloudUpload, &text);
+
+            parse_cloud_response(&text)
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncCloud for AsyncEdupage {
+    async fn upload(&self, path: PathBuf) -> Result<CloudFile, EdupageError> {
+        self.ensure_online()?;
+
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
         }
+
+        let file_name = upload_file_name(&path)?;
+        let contents = tokio::fs::read(&path).await.map_err(EdupageError::other)?;
+
+        self.with_reauthentication(|| async {
+            let url = self.url("/timeline/?akcia=uploadAtt")?;
+
+            let response = self
+                .send(upload_request(&url, &file_name, &contents)?)
+                .await?;
+
+            if is_login_redirect(response.url()) {
+                return Err(EdupageError::SessionExpired);
+            }
+
+            let text = response.text().map_err(EdupageError::parse)?;
+            self.capture(PayloadKind::CloudUpload, &text);
+
+            parse_cloud_response(&text)
+        })
+        .await
     }
 }
 
+#[cfg(feature = "blocking")]
 impl CloudFile {
     pub fn get_url(&self, edupage: Edupage) -> Result<String, EdupageError> {
         if !edupage.logged_in() {
             Err(EdupageError::NotLoggedIn)
         } else {
-            Ok(format!(
-                "https://{}.edupage.org{}",
-                edupage.subdomain.unwrap(),
-                self.file
-            ))
+            edupage.url(&self.file)
         }
     }
 }
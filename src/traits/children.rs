@@ -0,0 +1,120 @@
+use crate::{
+    edupage::{Edupage, EdupageError, RequestType},
+    types::{Student, UserID},
+};
+
+/// Lets parents see the data of all of their children.
+pub trait Children {
+    fn get_children(&self) -> Result<Vec<Student>, EdupageError>;
+    fn get_active_child(&self) -> Result<Option<Student>, EdupageError>;
+    fn switch_to_child(&mut self, child_id: i64) -> Result<(), EdupageError>;
+}
+
+impl Edupage {
+    /// Makes edupage send the data of the child with the id `child_id` in this session.
+    pub(crate) fn select_child(&self, subdomain: &str, child_id: i64) -> Result<(), EdupageError> {
+        let url = self.host.url(
+            subdomain,
+            &format!("/login/switchchild?studentid={}", child_id),
+        );
+
+        self.session_request(url, RequestType::GET, None, None)?;
+
+        Ok(())
+    }
+}
+
+impl Children for Edupage {
+    /// Get the children of the logged in parent.
+    ///
+    /// Returns an empty list if the logged in user is not a parent.
+    fn get_children(&self) -> Result<Vec<Student>, EdupageError> {
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
+        }
+
+        let data = match &self.data {
+            Some(x) => x,
+            None => return Err(EdupageError::NotLoggedIn),
+        };
+
+        let parent_id = match data.user_id {
+            UserID::Parent(id) => id,
+            _ => return Ok(Vec::new()),
+        };
+
+        Ok(data
+            .dbi
+            .students
+            .iter()
+            .filter(|student| student.is_child_of(parent_id))
+            .cloned()
+            .collect())
+    }
+
+    /// Get the child whose data is currently loaded.
+    ///
+    /// Returns `None` if no child was selected with [`Children::switch_to_child`], in that case
+    /// the data is for the child edupage shows by default.
+    fn get_active_child(&self) -> Result<Option<Student>, EdupageError> {
+        let child_id = match self.active_child {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        Ok(self
+            .get_children()?
+            .into_iter()
+            .find(|child| child.id == Some(child_id)))
+    }
+
+    /// Switch to another child of the logged in parent.
+    ///
+    /// The user's data is downloaded again, so the [`crate::traits::Timetable`],
+    /// [`crate::traits::Timeline`] and [`crate::traits::DBI`] methods return the data for the
+    /// selected child after this call.
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::{Children, Login, Timetable}};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// for child in edupage.get_children().unwrap() {
+    ///     edupage.switch_to_child(child.id.unwrap()).unwrap();
+    ///
+    ///     let today = chrono::Local::now().date_naive();
+    ///     println!("{} {}: {:?}", child.first_name, child.last_name, edupage.get_timetable(today));
+    /// }
+    /// ```
+    fn switch_to_child(&mut self, child_id: i64) -> Result<(), EdupageError> {
+        let is_child = self
+            .get_children()?
+            .iter()
+            .any(|child| child.id == Some(child_id));
+
+        if !is_child {
+            return Err(EdupageError::MissingData);
+        }
+
+        let subdomain = match &self.subdomain {
+            Some(s) => s.clone(),
+            None => return Err(EdupageError::NotLoggedIn),
+        };
+
+        let html = self.with_reauthentication(|| {
+            self.select_child(&subdomain, child_id)?;
+
+            match self.fetch_userhome(&subdomain)? {
+                Some(html) => Ok(html),
+                None => Err(EdupageError::SessionExpired),
+            }
+        })?;
+
+        self.parse_login_data(html)?;
+
+        self.active_child = Some(child_id);
+        Ok(())
+    }
+}
@@ -1,6 +1,32 @@
-use crate::{edupage::Edupage, edupage::EdupageError, types::dbi::*, types::person::*};
+use chrono::NaiveDate;
 
+use crate::{
+    edupage::EdupageError, edupage::UserDataSource, types::dbi::*, types::person::*,
+    types::search::SearchHit,
+};
+
+/// Looks up the school's teachers, students, parents, classes, subjects and classrooms.
+///
+/// The `get_*` methods return owned copies. Use [`DBI::dbi`] to borrow the data instead, its
+/// lookups by id do not need to go through all the entries.
 pub trait DBI {
+    /// Borrows the school's data, see the lookups of [`crate::types::dbi::DBI`].
+    ///
+    /// Example usage:
+    #[cfg_attr(feature = "blocking", doc = "```no_run")]
+    #[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+    /// use cdupage::{edupage::Edupage, traits::{Login, DBI}};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// let dbi = edupage.dbi().unwrap();
+    /// for student in dbi.class_students(1) {
+    ///     println!("{} {}", student.first_name, student.last_name);
+    /// }
+    /// ```
+    fn dbi(&self) -> Result<&crate::types::dbi::DBI, EdupageError>;
+
     fn get_teachers(&self) -> Result<Vec<Teacher>, EdupageError>;
     fn get_teacher_by_id(&self, id: i64) -> Result<Option<Teacher>, EdupageError>;
 
@@ -12,130 +38,151 @@ pub trait DBI {
 
     fn get_classrooms(&self) -> Result<Vec<DBIBase>, EdupageError>;
     fn get_classroom_by_id(&self, id: i64) -> Result<Option<DBIBase>, EdupageError>;
-}
 
-impl DBI for Edupage {
-    fn get_teachers(
-        &self,
-    ) -> Result<Vec<crate::types::person::Teacher>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_classes(&self) -> Result<Vec<Class>, EdupageError>;
+    fn get_class_by_id(&self, id: i64) -> Result<Option<Class>, EdupageError>;
 
-        let data = self.data.as_ref().unwrap();
+    /// Returns the students of the class with the id `class_id`.
+    fn get_class_students(&self, class_id: i64) -> Result<Vec<Student>, EdupageError>;
 
-        Ok(data.dbi.teachers.clone())
-    }
+    /// Returns the class teacher and the second class teacher (if the class has them)
+    /// of the class with the id `class_id`.
+    fn get_class_teachers(&self, class_id: i64) -> Result<Vec<Teacher>, EdupageError>;
+
+    /// Returns the home classroom of the class with the id `class_id`.
+    fn get_class_classroom(&self, class_id: i64) -> Result<Option<DBIBase>, EdupageError>;
+
+    fn get_parents(&self) -> Result<Vec<Parent>, EdupageError>;
+    fn get_parent_by_id(&self, id: i64) -> Result<Option<Parent>, EdupageError>;
+
+    /// Returns the parents of the student with the id `student_id`.
+    ///
+    /// Parents that are not in the school's data (e.g. they do not have an account) are left out,
+    /// so the result is empty for an unknown student.
+    fn get_student_parents(&self, student_id: i64) -> Result<Vec<Parent>, EdupageError>;
+
+    /// Returns the students that have the parent with the id `parent_id` as one of their parents.
+    fn get_parent_children(&self, parent_id: i64) -> Result<Vec<Student>, EdupageError>;
 
-    fn get_teacher_by_id(
+    /// Returns the teachers that worked at the school on `date`.
+    fn get_active_teachers(&self, date: NaiveDate) -> Result<Vec<Teacher>, EdupageError>;
+
+    /// Returns the students that were in the school on `date`.
+    fn get_active_students(&self, date: NaiveDate) -> Result<Vec<Student>, EdupageError>;
+
+    /// Returns the students of the class with the id `class_id` that were in the school on `date`.
+    fn get_class_students_on(
         &self,
-        id: i64,
-    ) -> Result<Option<crate::types::person::Teacher>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+        class_id: i64,
+        date: NaiveDate,
+    ) -> Result<Vec<Student>, EdupageError>;
 
-        let data = self.data.as_ref().unwrap();
+    /// Returns the teachers that are out, see [`crate::types::dbi::DBI::teachers_out`].
+    fn get_teachers_out(&self) -> Result<Vec<Teacher>, EdupageError>;
 
-        for teacher in data.dbi.teachers.clone() {
-            if teacher.id.is_some() && teacher.id.unwrap() == id {
-                return Ok(Some(teacher));
-            }
-        }
+    /// Searches the teachers, students, parents and subjects by name, see
+    /// [`crate::types::dbi::DBI::search`].
+    fn search(&self, query: &str) -> Result<Vec<SearchHit<'_>>, EdupageError>;
+}
 
-        Ok(None)
+impl<T: UserDataSource> DBI for T {
+    fn dbi(&self) -> Result<&crate::types::dbi::DBI, EdupageError> {
+        Ok(&self.user_data()?.dbi)
     }
 
-    fn get_students(
-        &self,
-    ) -> Result<Vec<crate::types::person::Student>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_teachers(&self) -> Result<Vec<Teacher>, EdupageError> {
+        Ok(self.dbi()?.teachers.clone())
+    }
 
-        let data = self.data.as_ref().unwrap();
+    fn get_teacher_by_id(&self, id: i64) -> Result<Option<Teacher>, EdupageError> {
+        Ok(self.dbi()?.teacher(id).cloned())
+    }
 
-        Ok(data.dbi.students.clone())
+    fn get_students(&self) -> Result<Vec<Student>, EdupageError> {
+        Ok(self.dbi()?.students.clone())
     }
 
-    fn get_student_by_id(
-        &self,
-        id: i64,
-    ) -> Result<Option<crate::types::person::Student>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_student_by_id(&self, id: i64) -> Result<Option<Student>, EdupageError> {
+        Ok(self.dbi()?.student(id).cloned())
+    }
+
+    fn get_subjects(&self) -> Result<Vec<DBIBase>, EdupageError> {
+        Ok(self.dbi()?.subjects.clone())
+    }
 
-        let data = self.data.as_ref().unwrap();
+    fn get_subject_by_id(&self, id: i64) -> Result<Option<DBIBase>, EdupageError> {
+        Ok(self.dbi()?.subject(id).cloned())
+    }
 
-        for student in data.dbi.students.clone() {
-            if student.id.is_some() && student.id.unwrap() == id {
-                return Ok(Some(student));
-            }
-        }
+    fn get_classrooms(&self) -> Result<Vec<DBIBase>, EdupageError> {
+        Ok(self.dbi()?.classrooms.clone())
+    }
 
-        Ok(None)
+    fn get_classroom_by_id(&self, id: i64) -> Result<Option<DBIBase>, EdupageError> {
+        Ok(self.dbi()?.classroom(id).cloned())
     }
 
-    fn get_subjects(
-        &self,
-    ) -> Result<Vec<crate::types::dbi::DBIBase>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_classes(&self) -> Result<Vec<Class>, EdupageError> {
+        Ok(self.dbi()?.classes.clone())
+    }
 
-        let data = self.data.as_ref().unwrap();
+    fn get_class_by_id(&self, id: i64) -> Result<Option<Class>, EdupageError> {
+        Ok(self.dbi()?.class(id).cloned())
+    }
 
-        Ok(data.dbi.subjects.clone())
+    fn get_class_students(&self, class_id: i64) -> Result<Vec<Student>, EdupageError> {
+        Ok(self.dbi()?.class_students(class_id).cloned().collect())
     }
 
-    fn get_subject_by_id(
-        &self,
-        id: i64,
-    ) -> Result<Option<crate::types::dbi::DBIBase>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_class_teachers(&self, class_id: i64) -> Result<Vec<Teacher>, EdupageError> {
+        Ok(self.dbi()?.class_teachers(class_id).cloned().collect())
+    }
+
+    fn get_class_classroom(&self, class_id: i64) -> Result<Option<DBIBase>, EdupageError> {
+        Ok(self.dbi()?.class_classroom(class_id).cloned())
+    }
 
-        let data = self.data.as_ref().unwrap();
+    fn get_parents(&self) -> Result<Vec<Parent>, EdupageError> {
+        Ok(self.dbi()?.parents.clone())
+    }
 
-        for subject in data.dbi.subjects.clone() {
-            if subject.id.is_some() && subject.id.unwrap() == id {
-                return Ok(Some(subject));
-            }
-        }
+    fn get_parent_by_id(&self, id: i64) -> Result<Option<Parent>, EdupageError> {
+        Ok(self.dbi()?.parent(id).cloned())
+    }
 
-        Ok(None)
+    fn get_student_parents(&self, student_id: i64) -> Result<Vec<Parent>, EdupageError> {
+        Ok(self.dbi()?.student_parents(student_id).cloned().collect())
     }
 
-    fn get_classrooms(
-        &self,
-    ) -> Result<Vec<crate::types::dbi::DBIBase>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
+    fn get_parent_children(&self, parent_id: i64) -> Result<Vec<Student>, EdupageError> {
+        Ok(self.dbi()?.parent_children(parent_id).cloned().collect())
+    }
 
-        let data = self.data.as_ref().unwrap();
+    fn get_active_teachers(&self, date: NaiveDate) -> Result<Vec<Teacher>, EdupageError> {
+        Ok(self.dbi()?.active_teachers(date).cloned().collect())
+    }
 
-        Ok(data.dbi.classrooms.clone())
+    fn get_active_students(&self, date: NaiveDate) -> Result<Vec<Student>, EdupageError> {
+        Ok(self.dbi()?.active_students(date).cloned().collect())
     }
 
-    fn get_classroom_by_id(
+    fn get_class_students_on(
         &self,
-        id: i64,
-    ) -> Result<Option<crate::types::dbi::DBIBase>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
-
-        let data = self.data.as_ref().unwrap();
+        class_id: i64,
+        date: NaiveDate,
+    ) -> Result<Vec<Student>, EdupageError> {
+        Ok(self
+            .dbi()?
+            .class_students_on(class_id, date)
+            .cloned()
+            .collect())
+    }
 
-        for classroom in data.dbi.classrooms.clone() {
-            if classroom.id.is_some() && classroom.id.unwrap() == id {
-                return Ok(Some(classroom));
-            }
-        }
+    fn get_teachers_out(&self) -> Result<Vec<Teacher>, EdupageError> {
+        Ok(self.dbi()?.teachers_out().cloned().collect())
+    }
 
-        Ok(None)
+    fn search(&self, query: &str) -> Result<Vec<SearchHit<'_>>, EdupageError> {
+        Ok(self.dbi()?.search(query))
     }
 }
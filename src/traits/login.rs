@@ -1,9 +1,26 @@
 use crate::edupage::EdupageError;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::transport::HttpResponse as Response;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
+#[cfg(any(feature = "blocking", feature = "async"))]
 use common_macros::hash_map;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::collections::HashMap;
+#[cfg(feature = "async")]
+use std::future::Future;
 
-use crate::edupage::{Edupage, RequestType};
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::edupage::RequestType;
+
+/// The subdomain of the EduPage portal, which can be used to log into any school.
+pub const PORTAL_SUBDOMAIN: &str = "login1";
 
 /// Logs in the user
 pub trait Login {
@@ -13,6 +30,86 @@ pub trait Login {
         username: &str,
         password: &str,
     ) -> Result<(), EdupageError>;
+
+    fn start_login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<LoginState, EdupageError>;
+
+    fn finish_login_with_code(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> Result<(), EdupageError>;
+
+    fn finish_login_if_confirmed(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<bool, EdupageError>;
+
+    fn resend_two_factor_notifications(
+        &self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<(), EdupageError>;
+
+    fn logout(&mut self) -> Result<(), EdupageError>;
+}
+
+/// The async version of [`Login`].
+#[cfg(feature = "async")]
+pub trait AsyncLogin {
+    fn login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> impl Future<Output = Result<(), EdupageError>> + Send;
+
+    fn start_login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> impl Future<Output = Result<LoginState, EdupageError>> + Send;
+
+    fn finish_login_with_code(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> impl Future<Output = Result<(), EdupageError>> + Send;
+
+    fn finish_login_if_confirmed(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+    ) -> impl Future<Output = Result<bool, EdupageError>> + Send;
+
+    fn resend_two_factor_notifications(
+        &self,
+        challenge: &TwoFactorChallenge,
+    ) -> impl Future<Output = Result<(), EdupageError>> + Send;
+
+    fn logout(&mut self) -> impl Future<Output = Result<(), EdupageError>> + Send;
+}
+
+/// The result of the first step of logging in.
+#[derive(Debug, Clone)]
+pub enum LoginState {
+    LoggedIn,
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+/// A pending 2 factor authentication challenge.
+///
+/// Edupage expects the code (or an approval from another device) to be submitted from the same
+/// session that started the login, so keep using the same `Edupage` instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwoFactorChallenge {
+    pub subdomain: String,
+    pub(crate) csrfauth: String,
+    pub(crate) authentication_endpoint: String,
+    pub(crate) authentication_token: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,92 +129,644 @@ impl LoginCredentials {
     }
 }
 
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Serialize, Debug)]
+struct TwoFactorCredentials<'a> {
+    csrfauth: &'a str,
+
+    #[serde(rename = "t2fasec")]
+    code: &'a str,
+
+    #[serde(rename = "2fNoSave")]
+    no_save: &'a str,
+
+    #[serde(rename = "2fform")]
+    form: &'a str,
+
+    #[serde(rename = "gu")]
+    authentication_endpoint: &'a str,
+
+    #[serde(rename = "au")]
+    authentication_token: &'a str,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Deserialize, Debug)]
+struct TwoFactorStatusResponse {
+    status: String,
+}
+
+/// Returns the value of a hidden `<input>` field, e.g. `name="csrfauth" value="..."`.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_input_value(html: &str, name: &str) -> Option<String> {
+    html.split(&format!("name=\"{}\" value=\"", name))
+        .nth(1)?
+        .split("\"")
+        .next()
+        .map(str::to_string)
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn is_captcha_url(url: &str) -> bool {
+    url.contains("cap=1") || url.contains("lerr=b43b43") || url.contains("rblckcptch")
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn form_headers() -> HashMap<String, String> {
+    hash_map! {
+        "Content-Type".to_string() => "application/x-www-form-urlencoded".to_string()
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_csrf_token(html: &str) -> Result<String, EdupageError> {
+    if !html.contains("csrfauth") {
+        return Err(EdupageError::InvalidResponse);
+    }
+
+    match parse_input_value(html, "csrfauth") {
+        Some(csrf_token) => Ok(csrf_token),
+        None => Err(EdupageError::ParseError(
+            "Failed to parse csrf token.".into(),
+        )),
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn credentials_post_data(
+    username: &str,
+    password: &str,
+    csrf_token: String,
+) -> Result<String, EdupageError> {
+    let login_credentials =
+        LoginCredentials::new(username.to_string(), password.to_string(), csrf_token);
+
+    serde_urlencoded::to_string(&login_credentials).map_err(EdupageError::serialization)
+}
+
+/// Checks where edupage redirected us after sending the login form.
+///
+/// Returns `false` if edupage asks for 2 factor authentication.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_login_url(url: &Url) -> Result<bool, EdupageError> {
+    let url = url.as_str();
+
+    if url.contains("bad=1") {
+        return Err(EdupageError::InvalidCredentials);
+    }
+
+    if is_captcha_url(url) {
+        return Err(EdupageError::CaptchaRequired);
+    }
+
+    Ok(!url.contains("twofactor"))
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_two_factor_challenge(
+    subdomain: &str,
+    html: &str,
+) -> Result<TwoFactorChallenge, EdupageError> {
+    let csrf_token = parse_csrf_token(html)?;
+    let authentication_endpoint = parse_input_value(html, "gu");
+    let authentication_token = parse_input_value(html, "au");
+
+    match (authentication_endpoint, authentication_token) {
+        (Some(authentication_endpoint), Some(authentication_token)) => Ok(TwoFactorChallenge {
+            subdomain: subdomain.to_string(),
+            csrfauth: csrf_token,
+            authentication_endpoint,
+            authentication_token,
+        }),
+        _ => Err(EdupageError::ParseError(
+            "Failed to parse the 2 factor authentication page.".into(),
+        )),
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn two_factor_post_data(
+    challenge: &TwoFactorChallenge,
+    code: &str,
+) -> Result<String, EdupageError> {
+    let credentials = TwoFactorCredentials {
+        csrfauth: &challenge.csrfauth,
+        code,
+        no_save: "y",
+        form: "1",
+        authentication_endpoint: &challenge.authentication_endpoint,
+        authentication_token: &challenge.authentication_token,
+    };
+
+    serde_urlencoded::to_string(&credentials).map_err(EdupageError::serialization)
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_two_factor_url(url: &Url) -> Result<(), EdupageError> {
+    let url = url.as_str();
+
+    if url.contains("bad=1") || url.contains("twofactor") {
+        Err(EdupageError::InvalidCredentials)
+    } else {
+        Ok(())
+    }
+}
+
+/// Edupage redirects to the school's subdomain when logging in through the portal.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn logged_in_subdomain(redirect_subdomain: Option<String>, subdomain: &str) -> String {
+    match redirect_subdomain {
+        Some(s) if s != PORTAL_SUBDOMAIN => s,
+        _ => subdomain.to_string(),
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn two_factor_action_path(action: &str) -> String {
+    format!("/login/twofactor?akcia={}", action)
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_confirmation_status(status: &TwoFactorStatusResponse) -> Result<bool, EdupageError> {
+    match status.status.as_str() {
+        "fail" => Ok(false),
+        "ok" => Ok(true),
+        _ => Err(EdupageError::InvalidResponse),
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_resend_status(status: &TwoFactorStatusResponse) -> Result<(), EdupageError> {
+    if status.status == "ok" {
+        Ok(())
+    } else {
+        Err(EdupageError::InvalidResponse)
+    }
+}
+
+/// `logout.php` answers with an error status when the session could not be ended.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn check_logout_response(response: &Response) -> Result<(), EdupageError> {
+    if response.status >= 400 {
+        return Err(EdupageError::http_status(response.status));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "blocking")]
+impl Edupage {
+    pub(crate) fn get_csrf_token(&self, url: String) -> Result<(String, String), EdupageError> {
+        self.ensure_online()?;
+
+        let result = self.request(url, RequestType::GET, None, None)?;
+
+        let response_text = match result.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+
+        let csrf_token = parse_csrf_token(&response_text)?;
+
+        Ok((csrf_token, response_text))
+    }
+
+    pub(crate) fn post_login_form(
+        &self,
+        subdomain: &str,
+        post_data: String,
+    ) -> Result<Response, EdupageError> {
+        self.ensure_online()?;
+
+        let url = self.host.url(subdomain, "/login/edubarLogin.php");
+
+        self.request(
+            url,
+            RequestType::POST,
+            Some(form_headers()),
+            Some(post_data),
+        )
+    }
+
+    /// Fetches a csrf token and sends the login form.
+    ///
+    /// Returns `None` if edupage asks for 2 factor authentication.
+    pub(crate) fn submit_credentials(
+        &self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<Response>, EdupageError> {
+        let url = self.host.url(subdomain, "/login/index.php");
+        let (csrf_token, _) = self.get_csrf_token(url)?;
+
+        let post_data = credentials_post_data(username, password, csrf_token)?;
+
+        let result = self.post_login_form(subdomain, post_data)?;
+
+        if !check_login_url(result.url())? {
+            return Ok(None);
+        }
+
+        Ok(Some(result))
+    }
+
+    pub(crate) fn finish_login(
+        &mut self,
+        subdomain: &str,
+        response: Response,
+    ) -> Result<(), EdupageError> {
+        let url = response.url().clone();
+
+        let response_text = match response.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+
+        self.finish_login_with_html(subdomain, &url, response_text)
+    }
+
+    pub(crate) fn finish_login_with_html(
+        &mut self,
+        subdomain: &str,
+        url: &Url,
+        html: String,
+    ) -> Result<(), EdupageError> {
+        let subdomain = logged_in_subdomain(self.host.subdomain_from_url(url), subdomain);
+
+        self.parse_login_data(html)?;
+
+        self.is_logged_in = true;
+        self.subdomain = Some(subdomain);
+        self.active_child = None;
+        Ok(())
+    }
+
+    fn submit_two_factor(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> Result<(), EdupageError> {
+        let post_data = two_factor_post_data(challenge, code)?;
+
+        let response = self.post_login_form(&challenge.subdomain, post_data)?;
+        check_two_factor_url(response.url())?;
+
+        self.finish_login(&challenge.subdomain, response)
+    }
+
+    fn two_factor_action(
+        &self,
+        challenge: &TwoFactorChallenge,
+        action: &str,
+    ) -> Result<TwoFactorStatusResponse, EdupageError> {
+        self.ensure_online()?;
+
+        let url = self
+            .host
+            .url(&challenge.subdomain, &two_factor_action_path(action));
+
+        let response = self.request(url, RequestType::POST, None, None)?;
+
+        response
+            .json::<TwoFactorStatusResponse>()
+            .map_err(EdupageError::parse)
+    }
+}
+
+#[cfg(feature = "blocking")]
 impl Login for Edupage {
     /// Login to Edupage.
     ///
-    /// This method does not support 2 factor authentication or captchas.
+    /// If the account has 2 factor authentication enabled, this method returns
+    /// [`EdupageError::TwoFactorRequired`]. Use [`Login::start_login`] to log into such accounts.
     ///  
-    /// You can use `login1` as the subdomain to login through the Edupage portal.
+    /// You can use `login1` as the subdomain to login through the Edupage portal. If the user
+    /// has accounts in multiple schools, use [`crate::traits::Portal::portal_login`] instead.
     fn login(
         &mut self,
         subdomain: &str,
         username: &str,
         password: &str,
     ) -> Result<(), EdupageError> {
-        let url = format!("https://{}.edupage.org/login/index.php", subdomain);
-
-        let result = self.request(url, RequestType::GET, None, None);
+        match self.start_login(subdomain, username, password)? {
+            LoginState::LoggedIn => Ok(()),
+            LoginState::TwoFactorRequired(_) => Err(EdupageError::TwoFactorRequired),
+        }
+    }
 
-        if let Err(error) = result {
-            return Err(EdupageError::HTTPError(error.to_string()));
+    /// Start logging in to Edupage.
+    ///
+    /// Returns [`LoginState::LoggedIn`] when the account does not use 2 factor authentication.
+    /// Otherwise a [`TwoFactorChallenge`] is returned, which can be completed with
+    /// [`Login::finish_login_with_code`] or [`Login::finish_login_if_confirmed`].
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::{Login, LoginState}};
+    ///
+    /// let mut edupage = Edupage::new();
+    ///
+    /// if let LoginState::TwoFactorRequired(challenge) = edupage.start_login("subdomain", "username", "password").unwrap() {
+    ///     let code = "123456"; // the code from the EduPage app or e-mail
+    ///     edupage.finish_login_with_code(&challenge, code).unwrap();
+    /// }
+    /// ```
+    fn start_login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<LoginState, EdupageError> {
+        if let Some(result) = self.submit_credentials(subdomain, username, password)? {
+            self.finish_login(subdomain, result)?;
+            return Ok(LoginState::LoggedIn);
         }
 
-        let result = result.unwrap();
-        let response_text = match result.text() {
-            Ok(x) => x,
-            Err(e) => return Err(EdupageError::HTTPError(e.to_string())),
-        };
+        let url = self.host.url(subdomain, "/login/twofactor?sn=1");
+        let (_, response_text) = self.get_csrf_token(url)?;
 
-        if !response_text.contains("csrfauth") {
-            return Err(EdupageError::InvalidResponse);
+        parse_two_factor_challenge(subdomain, &response_text).map(LoginState::TwoFactorRequired)
+    }
+
+    /// Finish logging in with the code sent by Edupage (in the EduPage app or in an e-mail).
+    fn finish_login_with_code(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> Result<(), EdupageError> {
+        self.submit_two_factor(challenge, code)
+    }
+
+    /// Check if the login was approved on another device and finish logging in if it was.
+    ///
+    /// Returns `false` if the login was not yet approved. You can call this method periodically
+    /// until it returns `true`.
+    fn finish_login_if_confirmed(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<bool, EdupageError> {
+        let response = self.two_factor_action(challenge, "checkIfConfirmed")?;
+
+        if !check_confirmation_status(&response)? {
+            return Ok(false);
         }
 
-        let csrf_token = match response_text.split("name=\"csrfauth\" value=\"").nth(1) {
-            Some(x) => x,
-            None => {
-                return Err(EdupageError::ParseError(
-                    "Failed to parse csrf token.".to_string(),
-                ))
+        self.submit_two_factor(challenge, "1")?;
+        Ok(true)
+    }
+
+    /// Ask Edupage to send the 2 factor authentication notifications again.
+    fn resend_two_factor_notifications(
+        &self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<(), EdupageError> {
+        let response = self.two_factor_action(challenge, "resendNotifs")?;
+
+        check_resend_status(&response)
+    }
+
+    /// Log out from Edupage.
+    ///
+    /// Ends the session on edupage's servers and clears all local session state (cookies,
+    /// the user's data and the credentials for [`crate::traits::Reauthentication`]).
+    ///
+    /// The local state is cleared even if edupage could not be reached, the error is returned
+    /// afterwards.
+    fn logout(&mut self) -> Result<(), EdupageError> {
+        let result = match &self.subdomain {
+            Some(subdomain) => {
+                let url = self.host.url(subdomain, "/login/logout.php");
+
+                self.request(url, RequestType::GET, None, None)
+                    .and_then(|response| check_logout_response(&response))
             }
-        }
-        .split("\"")
-        .nth(0)
-        .unwrap();
+            None => Ok(()),
+        };
+
+        self.cookies.clear();
+        self.set_gsec_hash(None);
+        self.is_logged_in = false;
+        self.data = None;
+        self.parse_warnings.clear();
+        self.subdomain = None;
+        self.active_child = None;
+        self.reauthentication = None;
+
+        result
+    }
+}
 
-        let login_credentials = LoginCredentials::new(
-            username.to_string(),
-            password.to_string(),
-            csrf_token.to_string(),
-        );
+#[cfg(feature = "async")]
+impl AsyncEdupage {
+    /// The async version of [`Edupage::get_csrf_token`].
+    pub(crate) async fn get_csrf_token(
+        &self,
+        url: String,
+    ) -> Result<(String, String), EdupageError> {
+        self.ensure_online()?;
 
-        let post_data = match serde_urlencoded::to_string(&login_credentials) {
+        let result = self.request(url, RequestType::GET, None, None).await?;
+
+        let response_text = match result.text() {
             Ok(x) => x,
-            Err(e) => return Err(EdupageError::SerializationError(e.to_string())),
+            Err(e) => return Err(EdupageError::parse(e)),
         };
 
-        let url = format!("https://{}.edupage.org/login/edubarLogin.php", subdomain);
+        let csrf_token = parse_csrf_token(&response_text)?;
+
+        Ok((csrf_token, response_text))
+    }
+
+    async fn post_login_form(
+        &self,
+        subdomain: &str,
+        post_data: String,
+    ) -> Result<Response, EdupageError> {
+        self.ensure_online()?;
+
+        let url = self.host.url(subdomain, "/login/edubarLogin.php");
 
-        let result = self.request(
+        self.request(
             url,
             RequestType::POST,
-            Some(hash_map! {
-                "Content-Type".to_string() => "application/x-www-form-urlencoded".to_string()
-            }),
+            Some(form_headers()),
             Some(post_data),
-        );
+        )
+        .await
+    }
 
-        if let Err(error) = result {
-            return Err(EdupageError::HTTPError(error.to_string()));
-        }
+    /// The async version of [`Edupage::submit_credentials`].
+    pub(crate) async fn submit_credentials(
+        &self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<Response>, EdupageError> {
+        let url = self.host.url(subdomain, "/login/index.php");
+        let (csrf_token, _) = self.get_csrf_token(url).await?;
+
+        let post_data = credentials_post_data(username, password, csrf_token)?;
 
-        let result = result.unwrap();
+        let result = self.post_login_form(subdomain, post_data).await?;
 
-        if result.url().as_str().contains("bad=1") {
-            return Err(EdupageError::InvalidCredentials);
+        if !check_login_url(result.url())? {
+            return Ok(None);
         }
 
-        let response_text = match result.text() {
+        Ok(Some(result))
+    }
+
+    fn finish_login(&mut self, subdomain: &str, response: Response) -> Result<(), EdupageError> {
+        let url = response.url().clone();
+
+        let html = match response.text() {
             Ok(x) => x,
-            Err(e) => return Err(EdupageError::HTTPError(e.to_string())),
+            Err(e) => return Err(EdupageError::parse(e)),
         };
 
-        match self.parse_login_data(response_text.to_string()) {
-            Ok(_) => {
-                self.is_logged_in = true;
-                self.subdomain = Some(subdomain.to_string());
-                Ok(())
-            }
-            Err(e) => Err(EdupageError::ParseError(e.to_string())),
+        let subdomain = logged_in_subdomain(self.host.subdomain_from_url(&url), subdomain);
+
+        self.parse_login_data(html)?;
+
+        self.is_logged_in = true;
+        self.subdomain = Some(subdomain);
+        Ok(())
+    }
+
+    async fn submit_two_factor(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> Result<(), EdupageError> {
+        let post_data = two_factor_post_data(challenge, code)?;
+
+        let response = self
+            .post_login_form(&challenge.subdomain, post_data)
+            .await?;
+        check_two_factor_url(response.url())?;
+
+        self.finish_login(&challenge.subdomain, response)
+    }
+
+    async fn two_factor_action(
+        &self,
+        challenge: &TwoFactorChallenge,
+        action: &str,
+    ) -> Result<TwoFactorStatusResponse, EdupageError> {
+        self.ensure_online()?;
+
+        let url = self
+            .host
+            .url(&challenge.subdomain, &two_factor_action_path(action));
+
+        let response = self.request(url, RequestType::POST, None, None).await?;
+
+        response
+            .json::<TwoFactorStatusResponse>()
+            .map_err(EdupageError::parse)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncLogin for AsyncEdupage {
+    /// Login to Edupage.
+    ///
+    /// See [`Login::login`].
+    async fn login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(), EdupageError> {
+        match self.start_login(subdomain, username, password).await? {
+            LoginState::LoggedIn => Ok(()),
+            LoginState::TwoFactorRequired(_) => Err(EdupageError::TwoFactorRequired),
         }
     }
+
+    /// Start logging in to Edupage.
+    ///
+    /// See [`Login::start_login`].
+    async fn start_login(
+        &mut self,
+        subdomain: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<LoginState, EdupageError> {
+        if let Some(result) = self
+            .submit_credentials(subdomain, username, password)
+            .await?
+        {
+            self.finish_login(subdomain, result)?;
+            return Ok(LoginState::LoggedIn);
+        }
+
+        let url = self.host.url(subdomain, "/login/twofactor?sn=1");
+        let (_, response_text) = self.get_csrf_token(url).await?;
+
+        parse_two_factor_challenge(subdomain, &response_text).map(LoginState::TwoFactorRequired)
+    }
+
+    /// Finish logging in with the code sent by Edupage (in the EduPage app or in an e-mail).
+    async fn finish_login_with_code(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+        code: &str,
+    ) -> Result<(), EdupageError> {
+        self.submit_two_factor(challenge, code).await
+    }
+
+    /// Check if the login was approved on another device and finish logging in if it was.
+    async fn finish_login_if_confirmed(
+        &mut self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<bool, EdupageError> {
+        let response = self
+            .two_factor_action(challenge, "checkIfConfirmed")
+            .await?;
+
+        if !check_confirmation_status(&response)? {
+            return Ok(false);
+        }
+
+        self.submit_two_factor(challenge, "1").await?;
+        Ok(true)
+    }
+
+    /// Ask Edupage to send the 2 factor authentication notifications again.
+    async fn resend_two_factor_notifications(
+        &self,
+        challenge: &TwoFactorChallenge,
+    ) -> Result<(), EdupageError> {
+        let response = self.two_factor_action(challenge, "resendNotifs").await?;
+
+        check_resend_status(&response)
+    }
+
+    /// Log out from Edupage.
+    ///
+    /// See [`Login::logout`].
+    async fn logout(&mut self) -> Result<(), EdupageError> {
+        let result = match &self.subdomain {
+            Some(subdomain) => {
+                let url = self.host.url(subdomain, "/login/logout.php");
+
+                self.request(url, RequestType::GET, None, None)
+                    .await
+                    .and_then(|response| check_logout_response(&response))
+            }
+            None => Ok(()),
+        };
+
+        self.cookies.clear();
+        self.set_gsec_hash(None);
+        self.is_logged_in = false;
+        self.data = None;
+        self.parse_warnings.clear();
+        self.subdomain = None;
+        self.reauthentication = None;
+
+        result
+    }
 }
@@ -1,15 +1,39 @@
 pub mod cdn;
+#[cfg(feature = "blocking")]
+pub mod children;
 pub mod dbi;
 pub mod login;
+#[cfg(feature = "blocking")]
+pub mod portal;
+pub mod profile;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub mod reauthentication;
+#[cfg(feature = "blocking")]
+pub mod refresh;
 pub mod ringing;
+pub mod rpc;
+#[cfg(feature = "blocking")]
+pub mod session;
 pub mod substitution;
 pub mod timeline;
 pub mod timetable;
 
 pub use cdn::*;
+#[cfg(feature = "blocking")]
+pub use children::*;
 pub use dbi::*;
 pub use login::*;
+#[cfg(feature = "blocking")]
+pub use portal::*;
+pub use profile::*;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub use reauthentication::*;
+#[cfg(feature = "blocking")]
+pub use refresh::*;
 pub use ringing::*;
+pub use rpc::*;
+#[cfg(feature = "blocking")]
+pub use session::*;
 pub use substitution::*;
 pub use timeline::*;
 pub use timetable::*;
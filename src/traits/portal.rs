@@ -0,0 +1,99 @@
+use crate::{
+    edupage::{Edupage, EdupageError},
+    traits::login::PORTAL_SUBDOMAIN,
+    types::{PortalAccount, PortalLoginState},
+};
+
+use crate::edupage::RequestType;
+
+/// Logs in through the EduPage portal, which supports users with accounts in multiple schools.
+pub trait Portal {
+    fn portal_login(
+        &mut self,
+        username: &str,
+        password: &str,
+    ) -> Result<PortalLoginState, EdupageError>;
+
+    fn open_portal_account(&mut self, account: &PortalAccount) -> Result<(), EdupageError>;
+}
+
+/// The account chooser page contains the accounts as a json array: `"accounts":[{...}, ...]`
+fn parse_portal_accounts(html: &str) -> Result<Vec<PortalAccount>, EdupageError> {
+    let accounts = match html.split("\"accounts\":").nth(1) {
+        Some(x) => x,
+        None => return Err(EdupageError::InvalidResponse),
+    };
+
+    match serde_json::Deserializer::from_str(accounts)
+        .into_iter::<Vec<PortalAccount>>()
+        .next()
+    {
+        Some(Ok(accounts)) => Ok(accounts),
+        Some(Err(e)) => Err(EdupageError::parse(e)),
+        None => Err(EdupageError::InvalidResponse),
+    }
+}
+
+impl Portal for Edupage {
+    /// Login through the EduPage portal.
+    ///
+    /// If the user has only one account, the session is opened right away and
+    /// [`PortalLoginState::LoggedIn`] is returned. Otherwise, the accounts the user can choose from
+    /// are returned and one of them can be opened with [`Portal::open_portal_account`].
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::Portal, types::PortalLoginState};
+    ///
+    /// let mut edupage = Edupage::new();
+    ///
+    /// if let PortalLoginState::AccountSelection(accounts) = edupage.portal_login("username", "password").unwrap() {
+    ///     let account = accounts.iter().find(|a| a.subdomain == "myschool").unwrap();
+    ///     edupage.open_portal_account(account).unwrap();
+    /// }
+    ///
+    /// println!("{:?}", edupage.subdomain);
+    /// ```
+    fn portal_login(
+        &mut self,
+        username: &str,
+        password: &str,
+    ) -> Result<PortalLoginState, EdupageError> {
+        let result = match self.submit_credentials(PORTAL_SUBDOMAIN, username, password)? {
+            Some(x) => x,
+            None => return Err(EdupageError::TwoFactorRequired),
+        };
+
+        let url = result.url().clone();
+
+        let response_text = match result.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+
+        // only one account - edupage redirected us straight to the school
+        if response_text.contains("userhome(") {
+            self.finish_login_with_html(PORTAL_SUBDOMAIN, &url, response_text)?;
+            return Ok(PortalLoginState::LoggedIn);
+        }
+
+        Ok(PortalLoginState::AccountSelection(parse_portal_accounts(
+            &response_text,
+        )?))
+    }
+
+    /// Open a full session for an account returned by [`Portal::portal_login`].
+    ///
+    /// After this call, [`Edupage::subdomain`] is set to the school of the chosen account.
+    fn open_portal_account(&mut self, account: &PortalAccount) -> Result<(), EdupageError> {
+        self.ensure_online()?;
+
+        let response = self.request(account.login_url.clone(), RequestType::GET, None, None)?;
+
+        if response.url().as_str().contains("/login/") {
+            return Err(EdupageError::InvalidResponse);
+        }
+
+        self.finish_login(&account.subdomain, response)
+    }
+}
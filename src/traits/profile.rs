@@ -0,0 +1,75 @@
+use crate::{
+    edupage::{EdupageError, UserDataSource},
+    types::{UserID, UserProfile},
+};
+
+/// Tells who is logged in.
+pub trait Profile {
+    /// Returns the logged in user's profile, with their class (students), home classroom and
+    /// classes (teachers) or children (parents) looked up in the DBI.
+    ///
+    /// Returns [`EdupageError::MissingData`] if the user is not a student, a teacher or a parent.
+    ///
+    /// Example usage:
+    #[cfg_attr(feature = "blocking", doc = "```no_run")]
+    #[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+    /// use cdupage::{edupage::Edupage, traits::{Login, Profile}, types::UserProfile};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// let me = edupage.me().unwrap();
+    /// if let UserProfile::Student { class: Some(class), .. } = &me {
+    ///     println!("Logged in as a student of {}", class.name);
+    /// }
+    ///
+    /// if me.capabilities().switch_children {
+    ///     // show the children
+    /// }
+    /// ```
+    fn me(&self) -> Result<UserProfile, EdupageError>;
+}
+
+impl<T: UserDataSource> Profile for T {
+    fn me(&self) -> Result<UserProfile, EdupageError> {
+        let data = self.user_data()?;
+        let dbi = &data.dbi;
+
+        let profile = match data.user_id {
+            UserID::Student(id) => {
+                let student = dbi.student(id);
+
+                UserProfile::Student {
+                    id,
+                    class: student.and_then(|s| dbi.class(s.class_id?)).cloned(),
+                    student: student.cloned(),
+                }
+            }
+            UserID::Teacher(id) => {
+                let teacher = dbi.teacher(id);
+
+                UserProfile::Teacher {
+                    id,
+                    classroom: teacher
+                        .and_then(|t| dbi.classroom(t.classroom_id?))
+                        .cloned(),
+                    classes: dbi
+                        .classes
+                        .iter()
+                        .filter(|c| [c.first_teacher_id, c.second_teacher_id].contains(&Some(id)))
+                        .cloned()
+                        .collect(),
+                    teacher: teacher.cloned(),
+                }
+            }
+            UserID::Parent(id) => UserProfile::Parent {
+                id,
+                parent: dbi.parent(id).cloned(),
+                children: dbi.parent_children(id).cloned().collect(),
+            },
+            _ => return Err(EdupageError::MissingData),
+        };
+
+        Ok(profile)
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+use crate::edupage::{parse_gsec_hash, EdupageError};
+use crate::transport::HttpResponse;
+
+/// Logs in again when the session expires, so long-running programs keep working.
+pub trait Reauthentication {
+    fn enable_reauthentication(&mut self, credentials: CredentialSource);
+    fn disable_reauthentication(&mut self);
+    fn reauthenticate(&self) -> Result<(), EdupageError>;
+}
+
+/// The async version of [`Reauthentication`].
+#[cfg(feature = "async")]
+pub trait AsyncReauthentication {
+    fn enable_reauthentication(&mut self, credentials: CredentialSource);
+    fn disable_reauthentication(&mut self);
+    fn reauthenticate(&self) -> impl Future<Output = Result<(), EdupageError>> + Send;
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub subdomain: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(subdomain: String, username: String, password: String) -> Self {
+        Self {
+            subdomain,
+            username,
+            password,
+        }
+    }
+}
+
+/// Where to get the credentials from when the session has to be renewed.
+#[derive(Clone)]
+pub enum CredentialSource {
+    /// The credentials are kept in memory.
+    Stored(Credentials),
+
+    /// The callback is called every time the session has to be renewed. Returning `None` gives up
+    /// and the original [`EdupageError::SessionExpired`] error is returned.
+    Callback(Arc<dyn Fn() -> Option<Credentials> + Send + Sync>),
+}
+
+impl CredentialSource {
+    fn credentials(&self) -> Option<Credentials> {
+        match self {
+            CredentialSource::Stored(credentials) => Some(credentials.clone()),
+            CredentialSource::Callback(callback) => callback(),
+        }
+    }
+}
+
+/// The credentials to log in again with and the subdomain of the session.
+fn reauthentication_target(
+    source: Option<&CredentialSource>,
+    subdomain: Option<&String>,
+) -> Result<(String, Credentials), EdupageError> {
+    let credentials = match source.and_then(CredentialSource::credentials) {
+        Some(credentials) => credentials,
+        None => return Err(EdupageError::SessionExpired),
+    };
+
+    let subdomain = match subdomain {
+        Some(s) => s.clone(),
+        None => credentials.subdomain.clone(),
+    };
+
+    Ok((subdomain, credentials))
+}
+
+/// Returns the gsec hash of the new session from the page edupage shows after logging in.
+fn reauthenticated_gsec_hash(response: Option<HttpResponse>) -> Result<String, EdupageError> {
+    let response = match response {
+        Some(x) => x,
+        None => return Err(EdupageError::TwoFactorRequired),
+    };
+
+    let response_text = match response.text() {
+        Ok(x) => x,
+        Err(e) => return Err(EdupageError::parse(e)),
+    };
+
+    match parse_gsec_hash(&response_text) {
+        Some(gsec_hash) => Ok(gsec_hash),
+        None => Err(EdupageError::ParseError("No gsechash in response!".into())),
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Edupage {
+    /// Runs `f` and if it fails because the session expired, logs in again (when enabled with
+    /// [`Reauthentication::enable_reauthentication`]) and runs `f` once more.
+    ///
+    /// `f` has to build the whole request again, as the gsec hash changes after logging in.
+    pub(crate) fn with_reauthentication<T>(
+        &self,
+        f: impl Fn() -> Result<T, EdupageError>,
+    ) -> Result<T, EdupageError> {
+        match f() {
+            Err(EdupageError::SessionExpired) if self.reauthentication.is_some() => {
+                self.reauthenticate()?;
+                f()
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Reauthentication for Edupage {
+    /// Enable logging in again when edupage reports that the session expired (redirects to the
+    /// login page or asks the client to reload). The original request is then retried once.
+    ///
+    /// Accounts with 2 factor authentication cannot be logged in again automatically.
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::{Credentials, CredentialSource, Login, Reauthentication}};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// edupage.enable_reauthentication(CredentialSource::Stored(Credentials::new(
+    ///     "subdomain".to_string(),
+    ///     "username".to_string(),
+    ///     "password".to_string(),
+    /// )));
+    /// ```
+    fn enable_reauthentication(&mut self, credentials: CredentialSource) {
+        self.reauthentication = Some(credentials);
+    }
+
+    fn disable_reauthentication(&mut self) {
+        self.reauthentication = None;
+    }
+
+    /// Log in again with the configured credentials.
+    ///
+    /// Only the session is renewed, the user's data loaded during login is kept. The child
+    /// selected with [`crate::traits::Children::switch_to_child`] is selected again.
+    fn reauthenticate(&self) -> Result<(), EdupageError> {
+        let (subdomain, credentials) =
+            reauthentication_target(self.reauthentication.as_ref(), self.subdomain.as_ref())?;
+
+        let response =
+            self.submit_credentials(&subdomain, &credentials.username, &credentials.password)?;
+        self.set_gsec_hash(Some(reauthenticated_gsec_hash(response)?));
+
+        // a new session starts with the child edupage shows by default
+        if let Some(child_id) = self.active_child {
+            self.select_child(&subdomain, child_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncEdupage {
+    /// The async version of [`Edupage::with_reauthentication`].
+    pub(crate) async fn with_reauthentication<T, F, Fut>(&self, f: F) -> Result<T, EdupageError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, EdupageError>>,
+    {
+        // the first result must not be kept across the awaits below, it might not be `Send`
+        match f().await {
+            Err(EdupageError::SessionExpired) if self.reauthentication.is_some() => {}
+            result => return result,
+        }
+
+        self.reauthenticate().await?;
+        f().await
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncReauthentication for AsyncEdupage {
+    /// See [`Reauthentication::enable_reauthentication`].
+    fn enable_reauthentication(&mut self, credentials: CredentialSource) {
+        self.reauthentication = Some(credentials);
+    }
+
+    fn disable_reauthentication(&mut self) {
+        self.reauthentication = None;
+    }
+
+    /// Log in again with the configured credentials.
+    ///
+    /// Only the session is renewed, the user's data loaded during login is kept.
+    async fn reauthenticate(&self) -> Result<(), EdupageError> {
+        let (subdomain, credentials) =
+            reauthentication_target(self.reauthentication.as_ref(), self.subdomain.as_ref())?;
+
+        let response = self
+            .submit_credentials(&subdomain, &credentials.username, &credentials.password)
+            .await?;
+        self.set_gsec_hash(Some(reauthenticated_gsec_hash(response)?));
+
+        Ok(())
+    }
+}
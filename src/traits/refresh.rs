@@ -0,0 +1,173 @@
+use serde::Serialize;
+
+use crate::{
+    edupage::{parse_userhome, Edupage, EdupageError},
+    parsing::ParseWarning,
+    types::UserData,
+};
+
+/// A part of the user's data, which can be refreshed on its own with
+/// [`Refresh::refresh_sections`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserDataSection {
+    /// The school's teachers, students, classes, ... ([`crate::traits::DBI`]).
+    Dbi,
+    /// The timeline items ([`crate::traits::Timeline`]).
+    Timeline,
+    /// The plan the timetables are built from ([`crate::traits::Timetable`]).
+    Timetable,
+    /// The ringing times ([`crate::traits::Ringing`]).
+    RingingTimes,
+    /// Today's and tomorrow's namedays.
+    Namedays,
+}
+
+impl UserDataSection {
+    pub const ALL: [UserDataSection; 5] = [
+        UserDataSection::Dbi,
+        UserDataSection::Timeline,
+        UserDataSection::Timetable,
+        UserDataSection::RingingTimes,
+        UserDataSection::Namedays,
+    ];
+
+    /// The keys of the section in edupage's json, used to match the parse warnings.
+    fn json_keys(&self) -> &'static [&'static str] {
+        match self {
+            UserDataSection::Dbi => &["dbi"],
+            UserDataSection::Timeline => &["items"],
+            UserDataSection::Timetable => &["dp"],
+            UserDataSection::RingingTimes => &["zvonenia"],
+            UserDataSection::Namedays => &["meninyDnes", "meninyZajtra"],
+        }
+    }
+
+    fn contains_warning(&self, warning: &ParseWarning) -> bool {
+        self.json_keys().iter().any(|key| {
+            warning
+                .path
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        })
+    }
+
+    /// Replaces the section of `data` with the one of `new_data`. Returns whether it changed.
+    fn replace(&self, data: &mut UserData, new_data: &UserData) -> bool {
+        match self {
+            UserDataSection::Dbi => replace_if_changed(&mut data.dbi, &new_data.dbi),
+            UserDataSection::Timeline => replace_if_changed(&mut data.items, &new_data.items),
+            UserDataSection::Timetable => replace_if_changed(&mut data.dp, &new_data.dp),
+            UserDataSection::RingingTimes => {
+                replace_if_changed(&mut data.ringing_times, &new_data.ringing_times)
+            }
+            UserDataSection::Namedays => {
+                let today = replace_if_changed(&mut data.nameday_today, &new_data.nameday_today);
+                let tomorrow =
+                    replace_if_changed(&mut data.nameday_tomorrow, &new_data.nameday_tomorrow);
+
+                today || tomorrow
+            }
+        }
+    }
+}
+
+/// The parsed types do not implement `PartialEq`, so they are compared as json.
+fn replace_if_changed<T: Serialize + Clone>(value: &mut T, new_value: &T) -> bool {
+    let changed = match (
+        serde_json::to_value(&*value),
+        serde_json::to_value(new_value),
+    ) {
+        (Ok(old), Ok(new)) => old != new,
+        _ => true,
+    };
+
+    if changed {
+        *value = new_value.clone();
+    }
+
+    changed
+}
+
+/// Loads the user's data again, without logging in again.
+///
+/// The user's data (timeline, plan, dbi, ...) is only loaded when logging in, so without a refresh,
+/// changes made later (like new timeline items) are not seen.
+pub trait Refresh {
+    /// Loads the whole user's data again with the current session.
+    ///
+    /// Returns the sections that changed.
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::{Login, Refresh, UserDataSection}};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// // later
+    /// if edupage.refresh().unwrap().contains(&UserDataSection::Timeline) {
+    ///     println!("There is something new in the timeline");
+    /// }
+    /// ```
+    fn refresh(&mut self) -> Result<Vec<UserDataSection>, EdupageError>;
+
+    /// Same as [`Refresh::refresh`], but only replaces `sections`, the rest of the data is kept.
+    ///
+    /// Edupage sends all the sections at once, so this does not make the request any smaller.
+    fn refresh_sections(
+        &mut self,
+        sections: &[UserDataSection],
+    ) -> Result<Vec<UserDataSection>, EdupageError>;
+}
+
+impl Refresh for Edupage {
+    fn refresh(&mut self) -> Result<Vec<UserDataSection>, EdupageError> {
+        self.refresh_sections(&UserDataSection::ALL)
+    }
+
+    fn refresh_sections(
+        &mut self,
+        sections: &[UserDataSection],
+    ) -> Result<Vec<UserDataSection>, EdupageError> {
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
+        }
+
+        let subdomain = match &self.subdomain {
+            Some(s) => s.clone(),
+            None => return Err(EdupageError::NotLoggedIn),
+        };
+
+        let html = self.with_reauthentication(|| match self.fetch_userhome(&subdomain)? {
+            Some(html) => Ok(html),
+            None => Err(EdupageError::SessionExpired),
+        })?;
+
+        let userhome = parse_userhome(&html, self.capture.as_ref(), self.parse_mode)?;
+
+        let data = match &mut self.data {
+            Some(data) => data,
+            None => return Err(EdupageError::NotLoggedIn),
+        };
+
+        let mut changed = Vec::new();
+        for section in UserDataSection::ALL {
+            if sections.contains(&section) && section.replace(data, &userhome.data) {
+                changed.push(section);
+            }
+        }
+
+        // the warnings of the sections that were not replaced still apply
+        self.parse_warnings
+            .retain(|w| !sections.iter().any(|s| s.contains_warning(w)));
+        self.parse_warnings.extend(
+            userhome
+                .warnings
+                .into_iter()
+                .filter(|w| sections.iter().any(|s| s.contains_warning(w))),
+        );
+        self.set_gsec_hash(Some(userhome.gsec_hash));
+
+        Ok(changed)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{edupage::Edupage, types::RingingTime};
+use crate::{edupage::UserDataSource, types::RingingTime};
 use chrono::{Local, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
@@ -23,12 +23,12 @@ impl RingingTime {
     }
 }
 
-impl Ringing for Edupage {
+impl<T: UserDataSource> Ringing for T {
     /// Get the start and end times for lessons. The lessons are in-order.
     fn get_ringing_times(&self) -> Vec<RingingTime> {
-        match &self.data {
-            Some(x) => x.ringing_times.clone(),
-            None => Vec::new(),
+        match self.user_data() {
+            Ok(x) => x.ringing_times.clone(),
+            Err(_) => Vec::new(),
         }
     }
 
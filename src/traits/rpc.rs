@@ -0,0 +1,279 @@
+#[cfg(any(feature = "blocking", feature = "async"))]
+use std::collections::HashMap;
+
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+use crate::edupage::EdupageError;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::{capture::PayloadKind, edupage::RequestType::POST};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use common_macros::hash_map;
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde_json::Value;
+#[cfg(feature = "async")]
+use std::future::Future;
+
+/// Calls the functions edupage's javascript calls on its servers (`/.../*.js?__func=...`).
+pub trait Rpc {
+    /// Calls `function` at `path` (e.g. `/substitution/server/viewer.js`) of the logged in school.
+    ///
+    /// `args` are sent as the `__args` of the call, usually a tuple or a `Vec`, which are
+    /// serialized as a json array. The gsec hash of the session is added automatically.
+    /// The `r` field of the reply is deserialized as `R`.
+    ///
+    /// Example usage:
+    #[cfg_attr(feature = "blocking", doc = "```no_run")]
+    #[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+    /// use cdupage::{edupage::Edupage, traits::{Login, Rpc}};
+    /// use serde_json::{json, Value};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// let html: String = edupage
+    ///     .rpc(
+    ///         "/substitution/server/viewer.js",
+    ///         "getSubstViewerDayDataHtml",
+    ///         (Value::Null, json!({ "date": "2024-09-02", "mode": "classes" })),
+    ///     )
+    ///     .unwrap();
+    /// ```
+    fn rpc<A: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        function: &str,
+        args: A,
+    ) -> Result<R, EdupageError>;
+}
+
+/// The async version of [`Rpc`].
+#[cfg(feature = "async")]
+pub trait AsyncRpc {
+    fn rpc<A: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        function: &str,
+        args: A,
+    ) -> impl Future<Output = Result<R, EdupageError>> + Send;
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Serialize)]
+struct RpcBody<'a, A: Serialize> {
+    __args: &'a A,
+    __gsh: &'a str,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn rpc_url(url: &str, function: &str) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+
+    format!("{}{}__func={}", url, separator, function)
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn rpc_headers() -> HashMap<String, String> {
+    hash_map! {
+        "Content-Type".to_string() => "application/json".to_string()
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn rpc_body<A: Serialize>(args: &A, gsec_hash: Option<&str>) -> Result<String, EdupageError> {
+    let gsec_hash = match gsec_hash {
+        Some(x) => x,
+        None => return Err(EdupageError::NotLoggedIn),
+    };
+
+    serde_json::to_string(&RpcBody {
+        __args: args,
+        __gsh: gsec_hash,
+    })
+    .map_err(EdupageError::serialization)
+}
+
+/// Edupage asks the client to reload the page with `{"reload": true}` when the session expired.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn is_reload_response(response: &HashMap<String, Value>) -> bool {
+    response
+        .get("reload")
+        .is_some_and(|reload| !reload.is_null() && reload != &Value::Bool(false))
+}
+
+/// Edupage replies with `{"r": ...}`, `{"reload": true}` when the session expired, or with the
+/// error in `e` (sometimes `err` or `error`).
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_rpc_response<R: DeserializeOwned>(text: &str) -> Result<R, EdupageError> {
+    let mut response =
+        serde_json::from_str::<HashMap<String, Value>>(text).map_err(EdupageError::parse)?;
+
+    if is_reload_response(&response) {
+        return Err(EdupageError::SessionExpired);
+    }
+
+    let error = ["e", "err", "error"]
+        .iter()
+        .filter_map(|key| response.get(*key))
+        .find(|error| !error.is_null());
+
+    if let Some(error) = error {
+        let message = match error {
+            Value::String(message) => message.clone(),
+            other => other.to_string(),
+        };
+
+        return Err(EdupageError::RpcError(message));
+    }
+
+    match response.remove("r") {
+        Some(r) => serde_json::from_value(r).map_err(EdupageError::serialization),
+        None => Err(EdupageError::InvalidResponse),
+    }
+}
+
+/// Some calls (like signing into an online lesson) do not reply with anything useful, so any
+/// reply except `{"reload": true}` means the call succeeded.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn check_rpc_reload(text: &str) -> Result<(), EdupageError> {
+    match serde_json::from_str::<HashMap<String, Value>>(text) {
+        Ok(response) if is_reload_response(&response) => Err(EdupageError::SessionExpired),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Edupage {
+    /// Calls `function` at `url` with `gsec_hash` (the session's one if `None`), captures the
+    /// reply as `kind` and returns it.
+    ///
+    /// The session is not renewed when it expired, the callers wrap the call in
+    /// [`Edupage::with_reauthentication`] themselves, together with anything the call depends
+    /// on (like a gsec hash fetched for it).
+    pub(crate) fn rpc_call<A: Serialize>(
+        &self,
+        url: &str,
+        function: &str,
+        args: &A,
+        gsec_hash: Option<&str>,
+        kind: PayloadKind,
+    ) -> Result<String, EdupageError> {
+        self.ensure_online()?;
+
+        let session_gsec_hash = self.gsec_hash();
+        let post_data = rpc_body(args, gsec_hash.or(session_gsec_hash.as_deref()))?;
+
+        let response = self.session_request(
+            rpc_url(url, function),
+            POST,
+            Some(rpc_headers()),
+            Some(post_data),
+        )?;
+
+        let text = match response.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+        self.capture(kind, &text);
+
+        Ok(text)
+    }
+
+    /// Same as [`Edupage::rpc_call`], but the `r` field of the reply is deserialized as `R`.
+    pub(crate) fn rpc_request<A: Serialize, R: DeserializeOwned>(
+        &self,
+        url: &str,
+        function: &str,
+        args: &A,
+        gsec_hash: Option<&str>,
+        kind: PayloadKind,
+    ) -> Result<R, EdupageError> {
+        let text = self.rpc_call(url, function, args, gsec_hash, kind)?;
+
+        parse_rpc_response(&text)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Rpc for Edupage {
+    fn rpc<A: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        function: &str,
+        args: A,
+    ) -> Result<R, EdupageError> {
+        let url = self.url(path)?;
+
+        self.with_reauthentication(|| {
+            self.rpc_request(&url, function, &args, None, PayloadKind::Rpc)
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncEdupage {
+    /// The async version of [`Edupage::rpc_call`].
+    pub(crate) async fn rpc_call<A: Serialize>(
+        &self,
+        url: &str,
+        function: &str,
+        args: &A,
+        gsec_hash: Option<&str>,
+        kind: PayloadKind,
+    ) -> Result<String, EdupageError> {
+        self.ensure_online()?;
+
+        let session_gsec_hash = self.gsec_hash();
+        let post_data = rpc_body(args, gsec_hash.or(session_gsec_hash.as_deref()))?;
+
+        let response = self
+            .session_request(
+                rpc_url(url, function),
+                POST,
+                Some(rpc_headers()),
+                Some(post_data),
+            )
+            .await?;
+
+        let text = match response.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+        self.capture(kind, &text);
+
+        Ok(text)
+    }
+
+    /// The async version of [`Edupage::rpc_request`].
+    pub(crate) async fn rpc_request<A: Serialize, R: DeserializeOwned>(
+        &self,
+        url: &str,
+        function: &str,
+        args: &A,
+        gsec_hash: Option<&str>,
+        kind: PayloadKind,
+    ) -> Result<R, EdupageError> {
+        let text = self.rpc_call(url, function, args, gsec_hash, kind).await?;
+
+        parse_rpc_response(&text)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRpc for AsyncEdupage {
+    async fn rpc<A: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        function: &str,
+        args: A,
+    ) -> Result<R, EdupageError> {
+        let url = self.url(path)?;
+
+        self.with_reauthentication(|| {
+            self.rpc_request(&url, function, &args, None, PayloadKind::Rpc)
+        })
+        .await
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::Utc;
+use reqwest::Url;
+
+use crate::{
+    edupage::{is_login_redirect, Edupage, EdupageBuilder, EdupageError, RequestType},
+    types::SessionSnapshot,
+};
+
+/// Saves and restores authenticated sessions, so the user does not have to log in again.
+pub trait Session {
+    fn export_session(&self) -> Result<SessionSnapshot, EdupageError>;
+
+    fn restore_session(snapshot: &SessionSnapshot) -> Result<Self, EdupageError>
+    where
+        Self: Sized;
+
+    fn resume_session(&mut self, snapshot: &SessionSnapshot) -> Result<(), EdupageError>;
+
+    fn is_session_valid(&self) -> Result<bool, EdupageError>;
+}
+
+impl Edupage {
+    fn subdomain_url(&self, subdomain: &str) -> Result<Url, EdupageError> {
+        Url::parse(&self.host.url(subdomain, "/")).map_err(EdupageError::parse)
+    }
+
+    /// Fetches the userhome page with the current session.
+    ///
+    /// Returns `None` if the session is not valid anymore (edupage redirected us to the login page).
+    pub(crate) fn fetch_userhome(&self, subdomain: &str) -> Result<Option<String>, EdupageError> {
+        self.ensure_online()?;
+
+        let url = self.host.url(subdomain, "/user/");
+
+        let response = self.request(url, RequestType::GET, None, None)?;
+
+        if is_login_redirect(response.url()) {
+            return Ok(None);
+        }
+
+        let response_text = match response.text() {
+            Ok(x) => x,
+            Err(e) => return Err(EdupageError::parse(e)),
+        };
+
+        if !response_text.contains("userhome(") {
+            return Ok(None);
+        }
+
+        Ok(Some(response_text))
+    }
+}
+
+impl EdupageBuilder {
+    /// Builds the `Edupage` and restores a session exported with [`Session::export_session`],
+    /// see [`Session::restore_session`].
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use cdupage::{edupage::Edupage, types::SessionSnapshot};
+    ///
+    /// let snapshot = std::fs::read_to_string("session.json").unwrap();
+    /// let snapshot: SessionSnapshot = serde_json::from_str(&snapshot).unwrap();
+    ///
+    /// let edupage = Edupage::builder()
+    ///     .timeout(Duration::from_secs(10))
+    ///     .restore_session(&snapshot)
+    ///     .unwrap();
+    /// ```
+    pub fn restore_session(self, snapshot: &SessionSnapshot) -> Result<Edupage, EdupageError> {
+        let mut edupage = self.build()?;
+        edupage.resume_session(snapshot)?;
+
+        Ok(edupage)
+    }
+}
+
+impl Session for Edupage {
+    /// Export the current session.
+    ///
+    /// The snapshot can be serialized (e.g. with `serde_json`) and later restored with
+    /// [`Session::restore_session`].
+    fn export_session(&self) -> Result<SessionSnapshot, EdupageError> {
+        if !self.is_logged_in {
+            return Err(EdupageError::NotLoggedIn);
+        }
+
+        let subdomain = match &self.subdomain {
+            Some(s) => s.clone(),
+            None => return Err(EdupageError::NotLoggedIn),
+        };
+
+        let url = self.subdomain_url(&subdomain)?;
+
+        Ok(SessionSnapshot {
+            cookies: self.cookies.export(&url),
+            gsec_hash: self.gsec_hash(),
+            subdomain,
+            taken_at: Utc::now().naive_utc(),
+        })
+    }
+
+    /// Create a logged in `Edupage` from a session exported with [`Session::export_session`].
+    ///
+    /// The user's data is downloaded again, so all other methods work the same way as after
+    /// logging in. Returns [`EdupageError::SessionExpired`] if the session is no longer valid.
+    ///
+    /// Example usage:
+    /// ```no_run
+    /// use cdupage::{edupage::Edupage, traits::{Login, Session}};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// let snapshot = serde_json::to_string(&edupage.export_session().unwrap()).unwrap();
+    ///
+    /// // ... later, possibly in a different process
+    /// let edupage = Edupage::restore_session(&serde_json::from_str(&snapshot).unwrap()).unwrap();
+    /// assert!(edupage.logged_in());
+    /// ```
+    ///
+    /// The `Edupage` uses the default configuration, use [`EdupageBuilder::restore_session`] to
+    /// configure it (or [`Session::resume_session`] on an existing one).
+    fn restore_session(snapshot: &SessionSnapshot) -> Result<Self, EdupageError> {
+        Edupage::builder().restore_session(snapshot)
+    }
+
+    /// Same as [`Session::restore_session`], but uses an existing `Edupage`, e.g. one created
+    /// with [`Edupage::builder`].
+    fn resume_session(&mut self, snapshot: &SessionSnapshot) -> Result<(), EdupageError> {
+        let url = self.subdomain_url(&snapshot.subdomain)?;
+        self.cookies.import(&url, &snapshot.cookies);
+        self.set_gsec_hash(snapshot.gsec_hash.clone());
+
+        let html = match self.fetch_userhome(&snapshot.subdomain)? {
+            Some(x) => x,
+            None => return Err(EdupageError::SessionExpired),
+        };
+
+        self.parse_login_data(html)?;
+
+        self.is_logged_in = true;
+        self.subdomain = Some(snapshot.subdomain.clone());
+        Ok(())
+    }
+
+    /// Check whether edupage still accepts the current session.
+    fn is_session_valid(&self) -> Result<bool, EdupageError> {
+        let subdomain = match &self.subdomain {
+            Some(s) => s,
+            None => return Ok(false),
+        };
+
+        Ok(self.fetch_userhome(subdomain)?.is_some())
+    }
+}
@@ -1,10 +1,15 @@
-use std::collections::HashMap;
-
-use crate::edupage::RequestType::POST;
-use crate::edupage::{Edupage, EdupageError};
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::capture::PayloadKind;
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+use crate::edupage::EdupageError;
 use chrono::NaiveDate;
-use common_macros::hash_map;
-use serde_json::Value;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde::Serialize;
+#[cfg(feature = "async")]
+use std::future::Future;
 
 pub trait Substitution {
     fn get_substitution_html(
@@ -14,6 +19,40 @@ pub trait Substitution {
     ) -> Result<String, EdupageError>;
 }
 
+/// The async version of [`Substitution`].
+#[cfg(feature = "async")]
+pub trait AsyncSubstitution {
+    fn get_substitution_html(
+        &self,
+        date: &NaiveDate,
+        subdomain: &str,
+    ) -> impl Future<Output = Result<String, EdupageError>> + Send;
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+const SUBSTITUTION_PATH: &str = "/substitution/server/viewer.js";
+#[cfg(any(feature = "blocking", feature = "async"))]
+const SUBSTITUTION_FUNCTION: &str = "getSubstViewerDayDataHtml";
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Serialize)]
+struct SubstitutionArgs {
+    date: String,
+    mode: &'static str,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn substitution_args(date: &NaiveDate) -> (Option<()>, SubstitutionArgs) {
+    (
+        None,
+        SubstitutionArgs {
+            date: date.format("%Y-%m-%d").to_string(),
+            mode: "classes",
+        },
+    )
+}
+
+#[cfg(feature = "blocking")]
 impl Substitution for Edupage {
     /// WIP: Gets the subsitution data for a date.
     ///
@@ -25,39 +64,42 @@ impl Substitution for Edupage {
         date: &NaiveDate,
         subdomain: &str,
     ) -> Result<String, EdupageError> {
-        let url = format!(
-            "https://{}.edupage.org/substitution/server/viewer.js?__func=getSubstViewerDayDataHtml",
-            subdomain
-        );
-
-        let headers = hash_map! {
-            "Content-Type".to_string() => "application/json".to_string()
-        };
-
-        // no point in trying to serialize, too complex structure
-        let post_data = format!(
-            "{{\"__args\":[null,{{\"date\":\"{}\",\"mode\":\"classes\"}}],\"__gsh\":\"{}\"}}",
-            date.format("%Y-%m-%d"),
-            self.gsec_hash.as_ref().unwrap()
-        );
-
-        let response = match self.request(url, POST, Some(headers), Some(post_data)) {
-            Ok(x) => x,
-            Err(e) => return Err(EdupageError::HTTPError(e)),
-        };
-
-        Ok(match response.json::<HashMap<String, Value>>() {
-            Ok(x) => {
-                let r = x.get("r");
-                if r.is_none() {
-                    return Err(EdupageError::SerializationError(
-                        "Failed to traverse json".to_string(),
-                    ));
-                }
-
-                r.unwrap().as_str().unwrap().to_string()
-            }
-            Err(e) => return Err(EdupageError::SerializationError(e.to_string())),
+        let url = self.host.url(subdomain, SUBSTITUTION_PATH);
+
+        self.with_reauthentication(|| {
+            self.rpc_request(
+                &url,
+                SUBSTITUTION_FUNCTION,
+                &substitution_args(date),
+                None,
+                PayloadKind::Substitution,
+            )
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncSubstitution for AsyncEdupage {
+    /// WIP: Gets the subsitution data for a date.
+    ///
+    /// See [`Substitution::get_substitution_html`].
+    async fn get_substitution_html(
+        &self,
+        date: &NaiveDate,
+        subdomain: &str,
+    ) -> Result<String, EdupageError> {
+        let url = self.host.url(subdomain, SUBSTITUTION_PATH);
+        let args = substitution_args(date);
+
+        self.with_reauthentication(|| {
+            self.rpc_request(
+                &url,
+                SUBSTITUTION_FUNCTION,
+                &args,
+                None,
+                PayloadKind::Substitution,
+            )
         })
+        .await
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    edupage::{Edupage, EdupageError},
+    edupage::{EdupageError, UserDataSource},
     types::timeline::*,
 };
 /// Gets the timeline from Edupage's servers
@@ -14,7 +14,7 @@ pub trait Timeline {
     ) -> Result<Vec<TimelineItem>, EdupageError>;
 }
 
-impl Timeline for Edupage {
+impl<T: UserDataSource> Timeline for T {
     /// Get timeline events (notifications) specifying the type of events you want.
     ///
     /// If you want multiple types of events, you can use [`Timeline::filter_timeline_by_item_types`].
@@ -22,11 +22,7 @@ impl Timeline for Edupage {
         &self,
         item_type: crate::types::timeline::TimelineItemType,
     ) -> Result<Vec<crate::types::TimelineItem>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
-
-        let data = self.data.as_ref().unwrap();
+        let data = self.user_data()?;
 
         let mut items = Vec::new();
         for item in data.items.clone() {
@@ -43,11 +39,7 @@ impl Timeline for Edupage {
         &self,
         item_types: Vec<crate::types::timeline::TimelineItemType>,
     ) -> Result<Vec<crate::types::TimelineItem>, crate::edupage::EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
-
-        let data = self.data.as_ref().unwrap();
+        let data = self.user_data()?;
 
         let mut items = Vec::new();
         for item in data.items.clone() {
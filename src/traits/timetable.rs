@@ -1,103 +1,100 @@
 use crate::{edupage::EdupageError, types::timetable::Timetable as EduTimetable};
 use chrono::{NaiveDate, NaiveDateTime};
-use serde::{Deserialize, Serialize};
-
-use crate::edupage::RequestType::POST;
-use crate::edupage::{Edupage, RequestType};
-use crate::traits::DBI;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use serde::Serialize;
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::capture::PayloadKind;
+#[cfg(feature = "async")]
+use crate::edupage::AsyncEdupage;
+#[cfg(feature = "blocking")]
+use crate::edupage::Edupage;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::edupage::RequestType;
+use crate::edupage::UserDataSource;
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::traits::rpc::check_rpc_reload;
 use crate::types::{dbi::DBIBase, person::Teacher, timetable::Lesson};
+#[cfg(any(feature = "blocking", feature = "async"))]
 use chrono::Utc;
-use common_macros::hash_map;
-use reqwest::Error;
-use std::collections::HashMap;
+#[cfg(feature = "async")]
+use std::future::Future;
 
 /// Gets today's timetable from EduPage's servers.
 pub trait Timetable {
     fn get_timetable(&self, date: NaiveDate) -> Result<EduTimetable, EdupageError>;
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct OnlineLessonErrorLoginResponse {
-    pub reload: bool,
+/// The async version of [`Lesson::sign_into_lesson`].
+#[cfg(feature = "async")]
+pub trait AsyncTimetable {
+    fn sign_into_lesson(
+        &self,
+        lesson: &Lesson,
+    ) -> impl Future<Output = Result<(), EdupageError>> + Send;
 }
 
-impl Timetable for Edupage {
+impl<T: UserDataSource> Timetable for T {
     /// Get the timetable for a given date.
     ///
     /// Note: Not all dates have available timetables - the data is pulled from edupage's user DP section.
     /// There is only data for a few days in advance.
     fn get_timetable(&self, date: NaiveDate) -> Result<EduTimetable, EdupageError> {
-        if !self.is_logged_in {
-            return Err(EdupageError::NotLoggedIn);
-        }
-
-        if self.data.is_none() {
-            return Err(EdupageError::NotLoggedIn);
-        }
-
-        let dp = self.data.clone().unwrap().dp;
+        let data = self.user_data()?;
+        let dp = &data.dp;
+        let dbi = &data.dbi;
 
         let ymd = date.format("%Y-%m-%d").to_string();
-        let plan = dp.dates.get(&ymd);
-
-        if plan.is_none() {
-            return Err(EdupageError::MissingData);
-        }
-
-        let plan = plan.unwrap();
+        let plan = match dp.dates.get(&ymd) {
+            Some(plan) => plan,
+            None => return Err(EdupageError::MissingData),
+        };
 
         let mut lessons: Vec<Lesson> = Vec::new();
-        for plan_item in plan.plan_items.clone().into_iter() {
-            if plan_item.header.is_empty() {
-                continue;
-            } else if plan_item.header[0].item.is_none() {
-                continue;
-            }
-
-            let teachers: Vec<Teacher> = if plan_item.teacher_ids.is_some() {
-                let ts = plan_item.teacher_ids.unwrap();
-                ts.iter()
-                    .flat_map(|t| self.get_teacher_by_id(*t))
-                    .flatten()
-                    .collect()
+        for plan_item in &plan.plan_items {
+            let header_item = match plan_item.header.first().and_then(|h| h.item.as_ref()) {
+                Some(item) => item,
+                None => continue,
+            };
+
+            let teachers: Vec<Teacher> = if let Some(ts) = &plan_item.teacher_ids {
+                ts.iter().filter_map(|t| dbi.teacher(*t)).cloned().collect()
             } else {
                 Vec::new()
             };
 
-            let classrooms: Vec<DBIBase> = if plan_item.classroom_ids.is_some() {
-                let cls_rooms = plan_item.classroom_ids.unwrap();
+            let classrooms: Vec<DBIBase> = if let Some(cls_rooms) = &plan_item.classroom_ids {
                 cls_rooms
                     .iter()
-                    .flat_map(|c| self.get_classroom_by_id(*c))
-                    .flatten()
+                    .filter_map(|c| dbi.classroom(*c))
+                    .cloned()
                     .collect()
             } else {
                 Vec::new()
             };
 
-            let subject_id = plan_item.header[0]
-                .item
-                .clone()
-                .unwrap()
-                .subject_id
-                .unwrap();
-            let subject = match self.get_subject_by_id(subject_id) {
-                Ok(s) => s,
-                Err(e) => return Err(e),
+            let subject_id = match header_item.subject_id {
+                Some(id) => id,
+                None => return Err(invalid_plan_item(&ymd, "subject")),
             };
-
-            let subject_name = match subject {
-                Some(s) => s.name,
+            let subject_name = match dbi.subject(subject_id) {
+                Some(s) => s.name.clone(),
                 None => return Err(EdupageError::MissingData),
             };
 
+            let (start_of_lesson, end_of_lesson) = match (plan_item.start_time, plan_item.end_time)
+            {
+                (Some(start), Some(end)) => (start, end),
+                _ => return Err(invalid_plan_item(&ymd, "start or end time")),
+            };
+
             lessons.push(Lesson {
                 teachers,
                 classrooms,
-                start_of_lesson: plan_item.start_time.unwrap(),
-                end_of_lesson: plan_item.end_time.unwrap(),
-                online_lesson_link: plan_item.online_link,
-                subject_id: plan_item.subject_id.unwrap(),
+                start_of_lesson,
+                end_of_lesson,
+                online_lesson_link: plan_item.online_link.clone(),
+                subject_id: plan_item.subject_id.unwrap_or(subject_id),
                 name: subject_name,
             })
         }
@@ -106,6 +103,10 @@ impl Timetable for Edupage {
     }
 }
 
+fn invalid_plan_item(date: &str, missing: &str) -> EdupageError {
+    EdupageError::ParseError(format!("A lesson on {} has no {}", date, missing).into())
+}
+
 impl Lesson {
     pub fn is_online_lesson(&self) -> bool {
         self.online_lesson_link.is_some()
@@ -114,77 +115,118 @@ impl Lesson {
     /// Report your presence on a lesson to Edupage.
     ///
     /// Produces the same result as when the online lesson link is clicked on the website.
+    #[cfg(feature = "blocking")]
     pub fn sign_into_lesson(&self, edupage: &Edupage) -> Result<(), EdupageError> {
         if !edupage.logged_in() {
             return Err(EdupageError::NotLoggedIn);
         }
 
-        let gsec_request_url = format!(
-            "https://{}.edupage.org/dashboard/eb.php",
-            edupage.subdomain.as_ref().unwrap()
-        );
+        edupage.with_reauthentication(|| {
+            let gsec_request_url = edupage.url("/dashboard/eb.php")?;
+
+            let gsec_hash_response =
+                edupage.session_request(gsec_request_url, RequestType::GET, None, None)?;
 
-        let gsec_hash_response =
-            match edupage.request(gsec_request_url, RequestType::GET, None, None) {
-                Ok(h) => h,
-                Err(_) => return Err(EdupageError::InvalidResponse),
+            let gsec_hash_response_text = match gsec_hash_response.text() {
+                Ok(x) => x,
+                Err(e) => return Err(EdupageError::parse(e)),
             };
+            let gsec_hash = parse_dashboard_gsec_hash(&gsec_hash_response_text)?;
 
-        let gsec_hash_response_text = gsec_hash_response.text().unwrap();
-        let gsec_hash = gsec_hash_response_text
-            .split("gsechash=")
-            .nth(1)
-            .unwrap()
-            .split("\"")
-            .nth(1)
-            .unwrap();
-
-        let request_url = format!(
-            "https://{}.edupage.org/dashboard/server/onlinelesson.js?__func=getOnlineLessonOpenUrl",
-            edupage.subdomain.clone().unwrap()
-        );
-
-        let today = Utc::now().naive_local();
-        let post_data = format!(
-            "{{[\
-            null,\
-            {{\
-                \"click\": true,
-                \"date\": \"{}\",
-                \"ol_url\": \"{}\",
-                \"subjectid\": \"{}\",
-            }}
-            ], \"__gsh\": \"{}\"}}",
-            today.format("%Y-%m-%d"),
-            &self.online_lesson_link.clone().unwrap(),
-            &self.subject_id,
-            gsec_hash
-        );
-
-        let response = edupage.request(
-            request_url,
-            POST,
-            Some(hash_map!(
-                "Content-Type".to_string() => "application/json".to_string()
-            )),
-            Some(post_data),
-        );
-
-        let json_result: Result<HashMap<String, serde_json::Value>, Error> = match response {
-            Ok(r) => r.json::<HashMap<String, serde_json::Value>>(),
-            Err(e) => return Err(EdupageError::HTTPError(e.to_string())),
-        };
+            let request_url = edupage.url(ONLINE_LESSON_PATH)?;
+
+            let response = edupage.rpc_call(
+                &request_url,
+                ONLINE_LESSON_FUNCTION,
+                &self.online_lesson_args(),
+                Some(&gsec_hash),
+                PayloadKind::OnlineLesson,
+            )?;
 
-        match json_result {
-            Ok(r) => {
-                if !r["reload"].is_null() {
-                    Err(EdupageError::InvalidResponse)
-                } else {
-                    Ok(())
-                }
-            }
-            Err(e) => Err(EdupageError::ParseError(e.to_string())),
+            check_rpc_reload(&response)
+        })
+    }
+
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    fn online_lesson_args(&self) -> (Option<()>, OnlineLessonArgs) {
+        (
+            None,
+            OnlineLessonArgs {
+                click: true,
+                date: Utc::now().naive_local().format("%Y-%m-%d").to_string(),
+                ol_url: self.online_lesson_link.clone().unwrap_or_default(),
+                subjectid: self.subject_id.to_string(),
+            },
+        )
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+const ONLINE_LESSON_PATH: &str = "/dashboard/server/onlinelesson.js";
+#[cfg(any(feature = "blocking", feature = "async"))]
+const ONLINE_LESSON_FUNCTION: &str = "getOnlineLessonOpenUrl";
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+#[derive(Serialize)]
+struct OnlineLessonArgs {
+    click: bool,
+    date: String,
+    ol_url: String,
+    subjectid: String,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn parse_dashboard_gsec_hash(html: &str) -> Result<String, EdupageError> {
+    match html
+        .split("gsechash=")
+        .nth(1)
+        .and_then(|x| x.split("\"").nth(1))
+    {
+        Some(x) => Ok(x.to_string()),
+        None => Err(EdupageError::ParseError("No gsechash in response!".into())),
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTimetable for AsyncEdupage {
+    /// Report your presence on a lesson to Edupage.
+    ///
+    /// Produces the same result as when the online lesson link is clicked on the website.
+    async fn sign_into_lesson(&self, lesson: &Lesson) -> Result<(), EdupageError> {
+        if !self.logged_in() {
+            return Err(EdupageError::NotLoggedIn);
         }
+
+        let args = lesson.online_lesson_args();
+
+        self.with_reauthentication(|| async {
+            let gsec_request_url = self.url("/dashboard/eb.php")?;
+
+            let gsec_hash_response = self
+                .session_request(gsec_request_url, RequestType::GET, None, None)
+                .await?;
+
+            let gsec_hash_response_text = match gsec_hash_response.text() {
+                Ok(x) => x,
+                Err(e) => return Err(EdupageError::parse(e)),
+            };
+            let gsec_hash = parse_dashboard_gsec_hash(&gsec_hash_response_text)?;
+
+            let request_url = self.url(ONLINE_LESSON_PATH)?;
+
+            let response = self
+                .rpc_call(
+                    &request_url,
+                    ONLINE_LESSON_FUNCTION,
+                    &args,
+                    Some(&gsec_hash),
+                    PayloadKind::OnlineLesson,
+                )
+                .await?;
+
+            check_rpc_reload(&response)
+        })
+        .await
     }
 }
 
@@ -236,14 +278,10 @@ impl EduTimetable {
     }
 
     pub fn get_first_lesson(&self) -> Option<Lesson> {
-        if !self.lessons.is_empty() {
-            return Some(self.lessons[0].clone());
-        }
-
-        None
+        self.lessons.first().cloned()
     }
 
     pub fn get_last_lesson(&self) -> Option<Lesson> {
-        return self.lessons.last().cloned();
+        self.lessons.last().cloned()
     }
 }
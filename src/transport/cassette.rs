@@ -0,0 +1,315 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "blocking")]
+use super::Transport;
+#[cfg(feature = "async")]
+use super::{AsyncTransport, TransportFuture};
+use super::{HttpRequest, HttpResponse};
+use crate::edupage::{EdupageError, RequestType};
+
+/// Replaces the values of the cookies, they are never needed for replaying.
+const REDACTED: &str = "REDACTED";
+
+/// Request/response pairs saved by [`RecordingTransport`] and replayed by [`ReplayTransport`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// The request headers are not recorded, they would only contain the session cookies.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: RequestType,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EdupageError> {
+        let contents = fs::read_to_string(path).map_err(EdupageError::other)?;
+
+        serde_json::from_str(&contents).map_err(EdupageError::parse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EdupageError> {
+        let contents = serde_json::to_string_pretty(self).map_err(EdupageError::serialization)?;
+
+        fs::write(path, contents).map_err(EdupageError::other)
+    }
+}
+
+/// A transport that sends the requests with another transport and records them, so they can
+/// be saved as a [`Cassette`] and replayed later (e.g. in tests) with [`ReplayTransport`].
+///
+/// It is a [`Transport`] when the inner transport is one and an [`AsyncTransport`] when the inner
+/// transport is an async one.
+///
+/// Cookie values are always redacted, other secrets (like the password) have to be registered
+/// with [`RecordingTransport::scrub`]. The responses are saved as they are, so check the
+/// cassette for personal data before sharing it.
+///
+/// Example usage:
+#[cfg_attr(feature = "blocking", doc = "```no_run")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use cdupage::{
+///     edupage::Edupage,
+///     traits::Login,
+///     transport::{RecordingTransport, ReqwestTransport},
+/// };
+///
+/// let recorder = RecordingTransport::new(ReqwestTransport::new().unwrap())
+///     .scrub("school", "demo")
+///     .scrub("username", "user")
+///     .scrub("password", "password");
+///
+/// let mut edupage = Edupage::builder()
+///     .transport(recorder.clone())
+///     .build()
+///     .unwrap();
+/// edupage.login("school", "username", "password").unwrap();
+///
+/// recorder.save("login.json").unwrap();
+/// ```
+pub struct RecordingTransport<T> {
+    inner: Arc<T>,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+    secrets: Vec<(String, String)>,
+}
+
+/// The clones share the recording, so one can be given to [`crate::edupage::EdupageBuilder`]
+/// and the other one used to save the cassette.
+impl<T> Clone for RecordingTransport<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            interactions: self.interactions.clone(),
+            secrets: self.secrets.clone(),
+        }
+    }
+}
+
+impl<T> RecordingTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            interactions: Arc::new(Mutex::new(Vec::new())),
+            secrets: Vec::new(),
+        }
+    }
+
+    /// Replace `secret` with `replacement` everywhere in the recording (urls, headers and
+    /// bodies), including its url encoded form.
+    pub fn scrub(mut self, secret: &str, replacement: &str) -> Self {
+        if secret.is_empty() {
+            return self;
+        }
+
+        self.secrets
+            .push((secret.to_string(), replacement.to_string()));
+
+        if let (Some(secret), Some(replacement)) = (url_encode(secret), url_encode(replacement)) {
+            self.secrets.push((secret, replacement));
+        }
+
+        self
+    }
+
+    /// Returns everything recorded so far, with the secrets scrubbed.
+    pub fn cassette(&self) -> Cassette {
+        let interactions = match self.interactions.lock() {
+            Ok(interactions) => interactions.clone(),
+            Err(_) => Vec::new(),
+        };
+
+        Cassette { interactions }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), EdupageError> {
+        self.cassette().save(path)
+    }
+
+    fn scrub_text(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, (secret, replacement)| {
+                text.replace(secret, replacement)
+            })
+    }
+
+    fn scrub_header(&self, name: &str, value: &str) -> (String, String) {
+        let value = if name.eq_ignore_ascii_case("set-cookie") {
+            redact_cookie(value)
+        } else {
+            self.scrub_text(value)
+        };
+
+        (name.to_string(), value)
+    }
+
+    fn record_request(&self, request: &HttpRequest) -> RecordedRequest {
+        RecordedRequest {
+            method: request.method,
+            url: self.scrub_text(request.url.as_str()),
+            body: request
+                .body
+                .as_ref()
+                .map(|body| self.scrub_text(&String::from_utf8_lossy(body))),
+        }
+    }
+
+    fn record(&self, request: RecordedRequest, response: &HttpResponse) {
+        let response = RecordedResponse {
+            status: response.status,
+            headers: response
+                .headers
+                .iter()
+                .map(|(name, value)| self.scrub_header(name, value))
+                .collect(),
+            body: self.scrub_text(&String::from_utf8_lossy(&response.body)),
+        };
+
+        if let Ok(mut interactions) = self.interactions.lock() {
+            interactions.push(Interaction { request, response });
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let recorded_request = self.record_request(&request);
+
+        let response = self.inner.send(request)?;
+        self.record(recorded_request, &response);
+
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport> AsyncTransport for RecordingTransport<T> {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let recorded_request = self.record_request(&request);
+
+            let response = self.inner.send(request).await?;
+            self.record(recorded_request, &response);
+
+            Ok(response)
+        })
+    }
+}
+
+/// A transport (both [`Transport`] and [`AsyncTransport`]) that answers with the responses from
+/// a [`Cassette`] instead of sending the requests.
+///
+/// Every recorded interaction is used once, in the order they were recorded, the request has to
+/// have the same method and url as the recorded one.
+pub struct ReplayTransport {
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+
+        Self {
+            interactions: cassette.interactions,
+            used: Mutex::new(used),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EdupageError> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+
+    fn replay(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let mut used = match self.used.lock() {
+            Ok(used) => used,
+            Err(_) => return Err(EdupageError::Other("The cassette is poisoned".into())),
+        };
+
+        let url = request.url.as_str();
+        let position = self
+            .interactions
+            .iter()
+            .zip(used.iter())
+            .position(|(x, used)| {
+                !used && x.request.method == request.method && x.request.url == url
+            });
+
+        let interaction = match position {
+            Some(i) => {
+                used[i] = true;
+                &self.interactions[i]
+            }
+            None => {
+                return Err(EdupageError::Other(
+                    format!("No recorded response for {:?} {}", request.method, url).into(),
+                ))
+            }
+        };
+
+        Ok(HttpResponse {
+            url: request.url,
+            status: interaction.response.status,
+            headers: interaction.response.headers.clone(),
+            body: interaction.response.body.clone().into_bytes(),
+        })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        self.replay(request)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for ReplayTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move { self.replay(request) })
+    }
+}
+
+fn url_encode(value: &str) -> Option<String> {
+    let encoded = serde_urlencoded::to_string([("", value)]).ok()?;
+
+    encoded.strip_prefix('=').map(str::to_string)
+}
+
+/// `name=value; path=/` => `name=REDACTED; path=/`
+fn redact_cookie(cookie: &str) -> String {
+    let (pair, attributes) = match cookie.split_once(';') {
+        Some((pair, attributes)) => (pair, Some(attributes)),
+        None => (cookie, None),
+    };
+
+    let name = pair.split('=').next().unwrap_or_default();
+
+    match attributes {
+        Some(attributes) => format!("{}={};{}", name, REDACTED, attributes),
+        None => format!("{}={}", name, REDACTED),
+    }
+}
@@ -0,0 +1,329 @@
+//! The HTTP layer used by `Edupage` and `AsyncEdupage`.
+use std::string::FromUtf8Error;
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    redirect::Policy,
+    Url,
+};
+use serde::de::DeserializeOwned;
+
+use crate::edupage::{ClientOptions, EdupageError, RequestType};
+
+pub mod cassette;
+pub mod rate_limit;
+pub mod retry;
+
+pub use cassette::*;
+pub use rate_limit::*;
+pub use retry::*;
+
+/// Sends HTTP requests for [`crate::edupage::Edupage`].
+///
+/// A transport sends exactly one request and returns the response as it is. Cookies and
+/// redirects are handled by `Edupage`, so a transport should not store cookies or follow
+/// redirects on its own.
+///
+/// [`ReqwestTransport`] is used by default, a custom transport (e.g. an in-memory fake for tests)
+/// can be set with [`crate::edupage::EdupageBuilder::transport`].
+///
+/// Example usage:
+/// ```
+/// use cdupage::{
+///     edupage::{Edupage, EdupageError},
+///     transport::{HttpRequest, HttpResponse, Transport},
+/// };
+///
+/// struct Offline;
+///
+/// impl Transport for Offline {
+///     fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+///         Ok(HttpResponse::new(request.url, 503))
+///     }
+/// }
+///
+/// let edupage = Edupage::builder().transport(Offline).build().unwrap();
+/// ```
+#[cfg(feature = "blocking")]
+pub trait Transport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError>;
+}
+
+/// The future returned by [`AsyncTransport::send`].
+#[cfg(feature = "async")]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<HttpResponse, EdupageError>> + Send + 'a>>;
+
+/// The async version of [`Transport`], which sends the requests for `AsyncEdupage`.
+///
+/// The future is boxed, so `AsyncEdupage` can keep any transport as a trait object.
+/// [`AsyncReqwestTransport`] is used by default, a custom transport can be set with
+/// [`crate::edupage::EdupageBuilder::async_transport`].
+///
+/// Example usage:
+/// ```
+/// use cdupage::{
+///     edupage::AsyncEdupage,
+///     transport::{AsyncTransport, HttpRequest, HttpResponse, TransportFuture},
+/// };
+///
+/// struct Offline;
+///
+/// impl AsyncTransport for Offline {
+///     fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+///         Box::pin(async move { Ok(HttpResponse::new(request.url, 503)) })
+///     }
+/// }
+///
+/// let edupage = AsyncEdupage::builder().async_transport(Offline).build_async().unwrap();
+/// ```
+#[cfg(feature = "async")]
+pub trait AsyncTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: RequestType,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// Returns the first header called `name` (case insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    /// The url of the request this response belongs to.
+    pub url: Url,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(url: Url, status: u16) -> Self {
+        Self {
+            url,
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the first header called `name` (case insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    pub fn is_redirect(&self) -> bool {
+        matches!(self.status, 301 | 302 | 303 | 307 | 308)
+    }
+
+    pub fn text(self) -> Result<String, FromUtf8Error> {
+        String::from_utf8(self.body)
+    }
+
+    pub fn json<T: DeserializeOwned>(self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// A transport that can not send anything, for the read-only `Edupage`s created from
+/// snapshots.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OfflineTransport;
+
+#[cfg(feature = "blocking")]
+impl Transport for OfflineTransport {
+    fn send(&self, _request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        Err(EdupageError::Offline)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for OfflineTransport {
+    fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async { Err(EdupageError::Offline) })
+    }
+}
+
+fn parse_headers(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn header_pair(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), EdupageError> {
+    let name = HeaderName::from_bytes(name.as_bytes()).map_err(EdupageError::other)?;
+    let value = HeaderValue::from_str(value).map_err(EdupageError::other)?;
+
+    Ok((name, value))
+}
+
+/// The default [`Transport`], which sends the requests with a blocking `reqwest` client.
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "blocking")]
+impl ReqwestTransport {
+    pub fn new() -> Result<Self, EdupageError> {
+        Self::from_options(&ClientOptions::default())
+    }
+
+    pub(crate) fn from_options(options: &ClientOptions) -> Result<Self, EdupageError> {
+        let client = options
+            .blocking_client_builder()?
+            .redirect(Policy::none())
+            .build()
+            .map_err(EdupageError::http)?;
+
+        Ok(Self { client })
+    }
+
+    /// Uses an already configured client.
+    ///
+    /// The client must not follow redirects (`redirect(Policy::none())`) and should not have
+    /// a cookie store, `Edupage` takes care of both.
+    pub fn with_client(client: reqwest::blocking::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Transport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let mut request_builder = match request.method {
+            RequestType::GET => self.client.get(request.url),
+            RequestType::POST => self.client.post(request.url),
+        };
+
+        for (name, value) in request.headers {
+            let (name, value) = header_pair(&name, &value)?;
+
+            request_builder = request_builder.header(name, value);
+        }
+
+        if let Some(body) = request.body {
+            request_builder = request_builder.body(body);
+        }
+
+        let response = request_builder.send().map_err(EdupageError::http)?;
+
+        let url = response.url().clone();
+        let status = response.status().as_u16();
+        let headers = parse_headers(response.headers());
+
+        let body = response.bytes().map_err(EdupageError::http)?.to_vec();
+
+        Ok(HttpResponse {
+            url,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// The default [`AsyncTransport`], which sends the requests with an async `reqwest` client.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+pub struct AsyncReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "async")]
+impl AsyncReqwestTransport {
+    pub fn new() -> Result<Self, EdupageError> {
+        Self::from_options(&ClientOptions::default())
+    }
+
+    pub(crate) fn from_options(options: &ClientOptions) -> Result<Self, EdupageError> {
+        let client = options
+            .async_client_builder()?
+            .redirect(Policy::none())
+            .build()
+            .map_err(EdupageError::http)?;
+
+        Ok(Self { client })
+    }
+
+    /// Uses an already configured client.
+    ///
+    /// The client must not follow redirects (`redirect(Policy::none())`) and should not have
+    /// a cookie store, `AsyncEdupage` takes care of both.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    async fn send_request(&self, request: HttpRequest) -> Result<HttpResponse, EdupageError> {
+        let mut request_builder = match request.method {
+            RequestType::GET => self.client.get(request.url),
+            RequestType::POST => self.client.post(request.url),
+        };
+
+        for (name, value) in request.headers {
+            let (name, value) = header_pair(&name, &value)?;
+
+            request_builder = request_builder.header(name, value);
+        }
+
+        if let Some(body) = request.body {
+            request_builder = request_builder.body(body);
+        }
+
+        let response = request_builder.send().await.map_err(EdupageError::http)?;
+
+        let url = response.url().clone();
+        let status = response.status().as_u16();
+        let headers = parse_headers(response.headers());
+
+        let body = response.bytes().await.map_err(EdupageError::http)?.to_vec();
+
+        Ok(HttpResponse {
+            url,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for AsyncReqwestTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(self.send_request(request))
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(feature = "blocking")]
+use std::thread;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A token bucket that limits how many requests are sent.
+///
+/// Every request takes one token, the bucket holds at most `burst` tokens and is refilled with
+/// `requests_per_second` tokens per second. When the bucket is empty, the request waits until a
+/// token is available.
+///
+/// Clones share the same bucket, so one limiter can be used by multiple `Edupage` or
+/// `AsyncEdupage` instances (e.g. all accounts of a bot talking to the same school).
+///
+/// Example usage (at most 2 requests per second, with bursts of up to 5 requests):
+#[cfg_attr(feature = "blocking", doc = "```")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use cdupage::{edupage::Edupage, transport::RateLimiter};
+///
+/// let edupage = Edupage::builder()
+///     .rate_limiter(RateLimiter::new(2.0, 5))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// `requests_per_second` has to be positive, otherwise the limiter does not limit anything.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+
+        Self {
+            requests_per_second,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// At most `requests` requests per minute, without bursts.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(f64::from(requests) / 60.0, 1)
+    }
+
+    /// Blocks until a request can be sent.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            thread::sleep(wait);
+        }
+    }
+
+    /// The async version of [`RateLimiter::acquire`].
+    #[cfg(feature = "async")]
+    pub(crate) async fn acquire_async(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token, or returns how long to wait for the next one.
+    pub(crate) fn try_acquire(&self) -> Option<Duration> {
+        if self.requests_per_second.is_nan() || self.requests_per_second <= 0.0 {
+            return None;
+        }
+
+        let mut bucket = self.bucket.lock().ok()?;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+
+        let wait = (1.0 - bucket.tokens) / self.requests_per_second;
+
+        Some(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use super::HttpResponse;
+use crate::edupage::{EdupageError, RequestType};
+
+/// When and how often failed requests are sent again.
+///
+/// A request is retried when it could not be sent because of the connection (e.g. it was refused
+/// or timed out, see [`crate::edupage::EdupageError::is_retryable`]) or when the response has one
+/// of the retryable status codes. The delay between the attempts grows exponentially
+/// (`initial_backoff * 2^attempt`, at most `max_backoff`), with jitter so many clients do not
+/// retry at the same time. A `Retry-After` header is respected.
+///
+/// By default, only `GET` requests are retried, twice, on `429`, `502`, `503` and `504`. `POST`
+/// requests can change something on edupage (like signing into a lesson), so sending them again
+/// has to be enabled with [`RetryPolicy::retry_post`].
+///
+/// Example usage:
+#[cfg_attr(feature = "blocking", doc = "```")]
+#[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+/// use std::time::Duration;
+/// use cdupage::{edupage::Edupage, transport::RetryPolicy};
+///
+/// let edupage = Edupage::builder()
+///     .retry_policy(
+///         RetryPolicy::new()
+///             .max_retries(5)
+///             .initial_backoff(Duration::from_secs(1))
+///             .retryable_statuses([500, 502, 503, 504]),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retryable_statuses: Vec<u16>,
+    retry_post: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+            retryable_statuses: vec![429, 502, 503, 504],
+            retry_post: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never retry, every request is sent once.
+    pub fn none() -> Self {
+        Self::default().max_retries(0)
+    }
+
+    /// How many times a request is retried (not counting the first attempt).
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The delay before the first retry.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// The longest delay between two attempts, also caps `Retry-After`.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Whether the delays are randomized (between half and the full delay). Enabled by default.
+    pub fn jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// The status codes after which the request is retried.
+    pub fn retryable_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.retryable_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Whether `POST` requests are retried too. Disabled by default, as edupage could have
+    /// already handled a request whose response was lost.
+    pub fn retry_post(mut self, enabled: bool) -> Self {
+        self.retry_post = enabled;
+        self
+    }
+
+    pub fn is_retryable_status(&self, status: u16) -> bool {
+        self.retryable_statuses.contains(&status)
+    }
+
+    pub fn is_retryable_method(&self, method: RequestType) -> bool {
+        match method {
+            RequestType::GET => true,
+            RequestType::POST => self.retry_post,
+        }
+    }
+
+    /// How many times a request sent with `method` may be retried.
+    pub(crate) fn retries_for(&self, method: RequestType) -> u32 {
+        if self.is_retryable_method(method) {
+            self.max_retries
+        } else {
+            0
+        }
+    }
+
+    /// Whether sending the request again could turn `result` into a success.
+    pub(crate) fn is_retryable_result(&self, result: &Result<HttpResponse, EdupageError>) -> bool {
+        match result {
+            Ok(response) => self.is_retryable_status(response.status),
+            Err(e) => e.is_retryable(),
+        }
+    }
+
+    /// The delay before retry number `attempt` (starting at 0).
+    pub(crate) fn backoff(&self, attempt: u32, response: Option<&HttpResponse>) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        let delay = if self.jitter {
+            let half = exponential / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            exponential
+        };
+
+        match response.and_then(retry_after) {
+            Some(retry_after) => delay.max(retry_after.min(self.max_backoff)),
+            None => delay,
+        }
+    }
+}
+
+/// Only the delay in seconds is supported, edupage does not send dates.
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    let seconds = response.header("retry-after")?.trim().parse::<u64>().ok()?;
+
+    Some(Duration::from_secs(seconds))
+}
+
+/// A random number in `[0, 1)`, good enough for jitter without depending on a random crate.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -1,3 +1,7 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use chrono::NaiveDate;
+
 use serde::{Deserialize, Serialize};
 
 use crate::deserializers::*;
@@ -51,10 +55,171 @@ pub struct DBI {
 
     #[cfg_attr(not(feature = "node-types"), serde(rename = "jeZUS"))]
     pub is_art_school: bool,
+
+    #[serde(skip)]
+    #[cfg_attr(feature = "node-types", ts(skip))]
+    index: OnceLock<DBIIndex>,
+}
+
+/// The positions of the entries in the vectors of [`DBI`], by their ids.
+#[derive(Clone, Debug, Default)]
+struct DBIIndex {
+    teachers: HashMap<i64, usize>,
+    classes: HashMap<i64, usize>,
+    subjects: HashMap<i64, usize>,
+    classrooms: HashMap<i64, usize>,
+    students: HashMap<i64, usize>,
+    parents: HashMap<i64, usize>,
+}
+
+fn index_by_id<T>(entries: &[T], id: impl Fn(&T) -> Option<i64>) -> HashMap<i64, usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(position, entry)| Some((id(entry)?, position)))
+        .collect()
+}
+
+impl DBIIndex {
+    fn new(dbi: &DBI) -> Self {
+        Self {
+            teachers: index_by_id(&dbi.teachers, |t| t.id),
+            classes: index_by_id(&dbi.classes, |c| c.id),
+            subjects: index_by_id(&dbi.subjects, |s| s.id),
+            classrooms: index_by_id(&dbi.classrooms, |c| c.id),
+            students: index_by_id(&dbi.students, |s| s.id),
+            parents: index_by_id(&dbi.parents, |p| p.id),
+        }
+    }
+}
+
+/// Lookups by id. They use an index that is built once (when the user's data is parsed), so they
+/// do not go through all the entries.
+///
+/// The index is not updated when the vectors are changed afterwards. The lookups notice when the
+/// entry they find through it does not match anymore and go through all the entries instead.
+///
+/// The students of a class and the children of a parent are found by going through the students
+/// once, as the class and the parents of a student can be changed in place.
+impl DBI {
+    fn index(&self) -> &DBIIndex {
+        self.index.get_or_init(|| DBIIndex::new(self))
+    }
+
+    /// Builds the index now instead of on the first lookup.
+    #[cfg(any(feature = "blocking", feature = "async"))]
+    pub(crate) fn build_index(&self) {
+        self.index();
+    }
+
+    pub fn teacher(&self, id: i64) -> Option<&Teacher> {
+        find(&self.teachers, &self.index().teachers, id, |t| t.id)
+    }
+
+    pub fn class(&self, id: i64) -> Option<&Class> {
+        find(&self.classes, &self.index().classes, id, |c| c.id)
+    }
+
+    pub fn subject(&self, id: i64) -> Option<&DBIBase> {
+        find(&self.subjects, &self.index().subjects, id, |s| s.id)
+    }
+
+    pub fn classroom(&self, id: i64) -> Option<&DBIBase> {
+        find(&self.classrooms, &self.index().classrooms, id, |c| c.id)
+    }
+
+    pub fn student(&self, id: i64) -> Option<&Student> {
+        find(&self.students, &self.index().students, id, |s| s.id)
+    }
+
+    pub fn parent(&self, id: i64) -> Option<&Parent> {
+        find(&self.parents, &self.index().parents, id, |p| p.id)
+    }
+
+    /// The students of the class with the id `class_id`.
+    pub fn class_students(&self, class_id: i64) -> impl Iterator<Item = &Student> {
+        self.students
+            .iter()
+            .filter(move |student| student.class_id == Some(class_id))
+    }
+
+    /// The students of the class with the id `class_id` that were in the school on `date`.
+    pub fn class_students_on(
+        &self,
+        class_id: i64,
+        date: NaiveDate,
+    ) -> impl Iterator<Item = &Student> {
+        self.class_students(class_id)
+            .filter(move |student| student.is_active_on(date))
+    }
+
+    /// The teachers that worked at the school on `date`, see [`Teacher::is_active_on`].
+    pub fn active_teachers(&self, date: NaiveDate) -> impl Iterator<Item = &Teacher> {
+        self.teachers
+            .iter()
+            .filter(move |teacher| teacher.is_active_on(date))
+    }
+
+    /// The students that were in the school on `date`, see [`Student::is_active_on`].
+    pub fn active_students(&self, date: NaiveDate) -> impl Iterator<Item = &Student> {
+        self.students
+            .iter()
+            .filter(move |student| student.is_active_on(date))
+    }
+
+    /// The teachers that are out (`is_out`). Edupage only says whether they are out at the time
+    /// the data was loaded.
+    pub fn teachers_out(&self) -> impl Iterator<Item = &Teacher> {
+        self.teachers.iter().filter(|teacher| teacher.is_out)
+    }
+
+    /// The class teacher and the second class teacher of the class with the id `class_id`.
+    pub fn class_teachers(&self, class_id: i64) -> impl Iterator<Item = &Teacher> {
+        self.class(class_id)
+            .into_iter()
+            .flat_map(|class| [class.first_teacher_id, class.second_teacher_id])
+            .flatten()
+            .filter_map(|id| self.teacher(id))
+    }
+
+    /// The home classroom of the class with the id `class_id`.
+    pub fn class_classroom(&self, class_id: i64) -> Option<&DBIBase> {
+        self.classroom(self.class(class_id)?.classroom_id?)
+    }
+
+    /// The parents of the student with the id `student_id` that are in the data.
+    pub fn student_parents(&self, student_id: i64) -> impl Iterator<Item = &Parent> {
+        self.student(student_id)
+            .into_iter()
+            .flat_map(Student::parent_ids)
+            .filter_map(|id| self.parent(id))
+    }
+
+    /// The students that have the parent with the id `parent_id` as one of their parents.
+    pub fn parent_children(&self, parent_id: i64) -> impl Iterator<Item = &Student> {
+        self.students
+            .iter()
+            .filter(move |student| student.parent_ids().any(|id| id == parent_id))
+    }
+}
+
+/// Finds the entry with the `id` using the `index`.
+fn find<'a, T>(
+    entries: &'a [T],
+    index: &HashMap<i64, usize>,
+    id: i64,
+    entry_id: impl Fn(&T) -> Option<i64>,
+) -> Option<&'a T> {
+    match index.get(&id).and_then(|position| entries.get(*position)) {
+        Some(entry) if entry_id(entry) == Some(id) => Some(entry),
+        // the vectors are public, so the entries could have been moved, added or removed since
+        // the index was built
+        _ => entries.iter().find(|entry| entry_id(entry) == Some(id)),
+    }
 }
 
 // only the base properties a lot dbi entries have in common
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(
     feature = "node-types",
     derive(TS),
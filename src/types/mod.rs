@@ -13,12 +13,20 @@ use crate::macro_aliases::*;
 pub mod cloud;
 pub mod dbi;
 pub mod person;
+pub mod portal;
+pub mod profile;
+pub mod search;
+pub mod session;
 pub mod timeline;
 pub mod timetable;
 
 pub use cloud::*;
 pub use dbi::*;
 pub use person::*;
+pub use portal::*;
+pub use profile::*;
+pub use search::*;
+pub use session::*;
 pub use timeline::*;
 pub use timetable::*;
 
@@ -72,6 +72,34 @@ pub struct Student {
     pub number_in_class: Option<i64>,
 }
 
+/// Whether `date` is between `from` and `to` (both inclusive), a missing bound is not checked.
+fn is_within(date: NaiveDate, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+    from.is_none_or(|from| from <= date) && to.is_none_or(|to| date <= to)
+}
+
+impl Student {
+    /// Whether the student was in the school on `date` (between `date_from` and `date_to`).
+    pub fn is_active_on(&self, date: NaiveDate) -> bool {
+        is_within(date, self.date_from, self.date_to)
+    }
+
+    /// The ids of the student's parents that are set.
+    pub fn parent_ids(&self) -> impl Iterator<Item = i64> {
+        [
+            self.first_parent_id,
+            self.second_parent_id,
+            self.third_parent_id,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Whether the parent with the id `parent_id` is one of the student's parents.
+    pub fn is_child_of(&self, parent_id: i64) -> bool {
+        self.parent_ids().any(|id| id == parent_id)
+    }
+}
+
 #[derive(Serde!, Serialize)]
 #[cfg_attr(
     feature = "node-types",
@@ -137,6 +165,15 @@ pub struct Teacher {
     pub date_to: Option<NaiveDate>,
 }
 
+impl Teacher {
+    /// Whether the teacher worked at the school on `date` (between `date_from` and `date_to`).
+    ///
+    /// Teachers that are out (see `is_out`) are still active.
+    pub fn is_active_on(&self, date: NaiveDate) -> bool {
+        is_within(date, self.date_from, self.date_to)
+    }
+}
+
 #[derive(Serde!, Serialize)]
 #[cfg_attr(
     feature = "node-types",
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::macro_aliases::*;
+
+use super::person::UserID;
+
+#[cfg(feature = "node-types")]
+use ts_rs::TS;
+
+/// An account offered by the EduPage portal (`login1.edupage.org`) when one person has
+/// accounts in multiple schools.
+#[derive(Serde!, Serialize)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub struct PortalAccount {
+    #[cfg_attr(not(feature = "node-types"), serde(rename = "edupage"))]
+    pub subdomain: String,
+
+    #[cfg_attr(not(feature = "node-types"), serde(rename = "userid"))]
+    pub user_id: UserID,
+
+    #[cfg_attr(not(feature = "node-types"), serde(rename = "meno"))]
+    pub name: String,
+
+    #[cfg_attr(not(feature = "node-types"), serde(rename = "url"))]
+    pub login_url: String,
+}
+
+/// The result of logging in through the EduPage portal.
+#[derive(Serde!, Serialize)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub enum PortalLoginState {
+    /// The user has only one account, the session is already opened for it.
+    LoggedIn,
+    /// The user has to choose one of these accounts with [`crate::traits::Portal::open_portal_account`].
+    AccountSelection(Vec<PortalAccount>),
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::macro_aliases::*;
+
+use super::dbi::DBIBase;
+use super::person::{Class, Parent, Student, Teacher};
+
+#[cfg(feature = "node-types")]
+use ts_rs::TS;
+
+/// The logged in user, with the related data from the DBI resolved.
+///
+/// The user's own entry (`student`, `teacher` or `parent`) is `None` when it is not in the
+/// school's data edupage sent.
+#[derive(Serde!, Serialize)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub enum UserProfile {
+    Student {
+        id: i64,
+        student: Option<Student>,
+        class: Option<Class>,
+    },
+    Teacher {
+        id: i64,
+        teacher: Option<Teacher>,
+        classroom: Option<DBIBase>,
+        /// The classes the teacher is the class teacher (or the second class teacher) of.
+        classes: Vec<Class>,
+    },
+    Parent {
+        id: i64,
+        parent: Option<Parent>,
+        children: Vec<Student>,
+    },
+}
+
+/// What the account can do, which depends on its role.
+#[derive(Copy, Serde!, Serialize, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub struct Capabilities {
+    /// Switching between children with [`crate::traits::Children::switch_to_child`] (parents).
+    pub switch_children: bool,
+    /// Signing into online lessons with [`crate::types::Lesson::sign_into_lesson`] (students).
+    pub sign_into_lessons: bool,
+    /// Being the class teacher of a class (teachers).
+    pub class_teacher: bool,
+}
+
+impl UserProfile {
+    /// The id of the user's student, teacher or parent entry.
+    pub fn id(&self) -> i64 {
+        match self {
+            UserProfile::Student { id, .. }
+            | UserProfile::Teacher { id, .. }
+            | UserProfile::Parent { id, .. } => *id,
+        }
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            switch_children: matches!(self, UserProfile::Parent { children, .. } if !children.is_empty()),
+            sign_into_lessons: matches!(self, UserProfile::Student { .. }),
+            class_teacher: matches!(self, UserProfile::Teacher { classes, .. } if !classes.is_empty()),
+        }
+    }
+}
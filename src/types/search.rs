@@ -0,0 +1,230 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use super::dbi::{DBIBase, DBI};
+use super::person::{Parent, Student, Teacher};
+
+/// What kind of entry a [`SearchHit`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DBIEntityKind {
+    Teacher,
+    Student,
+    Parent,
+    Subject,
+}
+
+/// An entry of the [`DBI`] found by [`DBI::search`].
+#[derive(Debug, Clone, Copy)]
+pub enum DBIEntity<'a> {
+    Teacher(&'a Teacher),
+    Student(&'a Student),
+    Parent(&'a Parent),
+    Subject(&'a DBIBase),
+}
+
+impl DBIEntity<'_> {
+    pub fn kind(&self) -> DBIEntityKind {
+        match self {
+            DBIEntity::Teacher(_) => DBIEntityKind::Teacher,
+            DBIEntity::Student(_) => DBIEntityKind::Student,
+            DBIEntity::Parent(_) => DBIEntityKind::Parent,
+            DBIEntity::Subject(_) => DBIEntityKind::Subject,
+        }
+    }
+
+    pub fn id(&self) -> Option<i64> {
+        match self {
+            DBIEntity::Teacher(t) => t.id,
+            DBIEntity::Student(s) => s.id,
+            DBIEntity::Parent(p) => p.id,
+            DBIEntity::Subject(s) => s.id,
+        }
+    }
+
+    /// The full name of the person or the name of the subject.
+    pub fn name(&self) -> String {
+        match self {
+            DBIEntity::Teacher(t) => format!("{} {}", t.first_name, t.last_name),
+            DBIEntity::Student(s) => format!("{} {}", s.first_name, s.last_name),
+            DBIEntity::Parent(p) => format!("{} {}", p.first_name, p.last_name),
+            DBIEntity::Subject(s) => s.name.clone(),
+        }
+    }
+
+    /// The names the entry can be found by.
+    fn search_names(&self) -> Vec<&str> {
+        match self {
+            DBIEntity::Teacher(t) => vec![&t.first_name, &t.last_name, &t.short],
+            DBIEntity::Student(s) => vec![&s.first_name, &s.last_name],
+            DBIEntity::Parent(p) => vec![&p.first_name, &p.last_name],
+            DBIEntity::Subject(s) => vec![&s.name, &s.short],
+        }
+    }
+}
+
+/// How well a [`SearchHit`] matches the query, from the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MatchKind {
+    /// Every word of the query is a whole name, e.g. `novak` for `Novák`.
+    Exact,
+    /// Every word of the query is the start of a name, e.g. `nov` for `Novák`.
+    Prefix,
+    /// Some words of the query are a name (or the start of it) with a typo, e.g. `nvoak` for `Novák`.
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchHit<'a> {
+    pub entity: DBIEntity<'a>,
+    pub match_kind: MatchKind,
+    /// How many typos there are in the query, 0 unless the `match_kind` is [`MatchKind::Fuzzy`].
+    pub typos: usize,
+}
+
+impl SearchHit<'_> {
+    pub fn kind(&self) -> DBIEntityKind {
+        self.entity.kind()
+    }
+}
+
+/// Lowercases `s` and removes the diacritics, so `Ľudovít` becomes `ludovit`.
+fn normalize(s: &str) -> String {
+    s.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            // letters with a stroke do not decompose
+            'ł' => 'l',
+            'đ' => 'd',
+            'ø' => 'o',
+            c => c,
+        })
+        .collect()
+}
+
+fn words(s: &str) -> Vec<Vec<char>> {
+    normalize(s)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.chars().collect())
+        .collect()
+}
+
+/// The number of insertions, deletions, substitutions and swaps of two adjacent characters
+/// needed to turn `a` into `b` (the optimal string alignment distance).
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous_row: Vec<usize> = Vec::new();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut next_row = vec![i; b.len() + 1];
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            next_row[j] = (row[j] + 1).min(next_row[j - 1] + 1).min(row[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                next_row[j] = next_row[j].min(previous_row[j - 2] + 1);
+            }
+        }
+
+        previous_row = std::mem::replace(&mut row, next_row);
+    }
+
+    row[b.len()]
+}
+
+/// How well the word of the query matches the word of a name.
+fn match_word(query: &[char], word: &[char]) -> Option<(MatchKind, usize)> {
+    if query == word {
+        return Some((MatchKind::Exact, 0));
+    }
+
+    if word.starts_with(query) {
+        return Some((MatchKind::Prefix, 0));
+    }
+
+    // one typo for every 4 characters, so short words have to be typed correctly
+    let allowed_typos = query.len() / 4;
+    if allowed_typos == 0 {
+        return None;
+    }
+
+    let prefix = &word[..word.len().min(query.len())];
+    let typos = edit_distance(query, word).min(edit_distance(query, prefix));
+
+    (typos <= allowed_typos).then_some((MatchKind::Fuzzy, typos))
+}
+
+/// Every word of the query has to match one of the `words`, the worst match counts.
+fn match_words(query: &[Vec<char>], words: &[Vec<char>]) -> Option<(MatchKind, usize)> {
+    let mut match_kind = MatchKind::Exact;
+    let mut typos = 0;
+
+    for query_word in query {
+        let (word_match_kind, word_typos) = words
+            .iter()
+            .filter_map(|word| match_word(query_word, word))
+            .min()?;
+
+        match_kind = match_kind.max(word_match_kind);
+        typos += word_typos;
+    }
+
+    Some((match_kind, typos))
+}
+
+impl DBI {
+    /// Searches the teachers, students, parents and subjects by their names (and short names),
+    /// ignoring diacritics and case, so `novak` finds `Novák`.
+    ///
+    /// Every word of the query has to match a name exactly, be the start of one or have a few
+    /// typos (one for every 4 characters). The best matches are first.
+    ///
+    /// Example usage:
+    #[cfg_attr(feature = "blocking", doc = "```no_run")]
+    #[cfg_attr(not(feature = "blocking"), doc = "```ignore")]
+    /// use cdupage::{edupage::Edupage, traits::{Login, DBI}, types::DBIEntity};
+    ///
+    /// let mut edupage = Edupage::new();
+    /// edupage.login("subdomain", "username", "password").unwrap();
+    ///
+    /// for hit in edupage.dbi().unwrap().search("jan novak") {
+    ///     if let DBIEntity::Teacher(teacher) = hit.entity {
+    ///         println!("{} ({:?})", teacher.short, hit.match_kind);
+    ///     }
+    /// }
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let query = words(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let entities = self
+            .teachers
+            .iter()
+            .map(DBIEntity::Teacher)
+            .chain(self.students.iter().map(DBIEntity::Student))
+            .chain(self.parents.iter().map(DBIEntity::Parent))
+            .chain(self.subjects.iter().map(DBIEntity::Subject));
+
+        let mut hits: Vec<SearchHit> = entities
+            .filter_map(|entity| {
+                let words: Vec<Vec<char>> =
+                    entity.search_names().into_iter().flat_map(words).collect();
+
+                let (match_kind, typos) = match_words(&query, &words)?;
+
+                Some(SearchHit {
+                    entity,
+                    match_kind,
+                    typos,
+                })
+            })
+            .collect();
+
+        hits.sort_by_cached_key(|hit| (hit.match_kind, hit.typos, normalize(&hit.entity.name())));
+
+        hits
+    }
+}
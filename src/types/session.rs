@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::macro_aliases::*;
+
+#[cfg(feature = "node-types")]
+use ts_rs::TS;
+
+#[derive(Serde!, Serialize)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub struct SessionCookie {
+    pub name: String,
+    pub value: String,
+}
+
+/// Everything that is needed to restore an authenticated session without logging in again.
+///
+/// **Warning!** The snapshot contains the session cookies, anybody who has it can act as the
+/// logged in user until the session expires. Store it as you would store a password.
+#[derive(Serde!, Serialize)]
+#[cfg_attr(
+    feature = "node-types",
+    derive(TS),
+    ts(export),
+    ts(rename_all = "camelCase")
+)]
+pub struct SessionSnapshot {
+    pub cookies: Vec<SessionCookie>,
+    pub gsec_hash: Option<String>,
+    pub subdomain: String,
+
+    /// When the snapshot was taken (UTC).
+    pub taken_at: NaiveDateTime,
+}